@@ -0,0 +1,939 @@
+use log::LevelFilter;
+
+use crate::{ColorMode, ColorScope, DefaultFormatter, Error, Formatter, JsonFormatter,
+            LogfmtFormatter, LoggerHandle, Palette, ParseError, PatternFormatter, SimpleLogger,
+            Sink};
+use crate::file::Rotation;
+use crate::filter::Filter;
+use crate::format::Source;
+use crate::handle::Settings;
+use crate::timestamp::{Clock, Precision, TimestampFormat};
+use std::convert::Infallible;
+use std::env;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The environment variable used by default to set the log level, as used by many other loggers
+pub const RUST_LOG_ENV: &str = "RUST_LOG";
+
+/// An alternative environment variable to set the log level of a `SimpleLogger`, for when
+/// `RUST_LOG` is already used to configure other loggers
+pub const SIMPLOG_LEVEL_ENV: &str = "SIMPLOG_LEVEL";
+
+/// The output stream that log lines are written to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// Write log output to STDOUT, except for the levels selected by
+    /// `SimpleLoggerBuilder::stderr_level()` which are written to STDERR
+    Stdout,
+    /// Write all log output to STDERR
+    Stderr,
+}
+
+/// A builder for a `SimpleLogger`, obtained via `SimpleLogger::builder()`.
+///
+/// Each option has a setter that can be chained, and the builder is finished with either
+/// `build()` to get a `SimpleLogger`, or `init()`/`try_init()` to install it as the logger
+/// used by the `log` framework.
+///
+/// # Example
+/// ```
+/// use log::{info, LevelFilter};
+/// use simplog::{SimpleLogger, Target};
+///
+/// SimpleLogger::builder()
+///     .level(LevelFilter::Info)
+///     .prefix(false)
+///     .timestamp(true)
+///     .target(Target::Stderr)
+///     .init();
+/// info!("Hello World!");
+/// // Produces "1.246717ms Hello World!" on STDERR
+/// ```
+#[derive(Clone)]
+pub struct SimpleLoggerBuilder {
+    filter: Filter,
+    env: Option<String>,
+    strict: bool,
+    parse_error: Option<ParseError>,
+    prefix: bool,
+    timestamp: bool,
+    timestamp_format: TimestampFormat,
+    precision: Precision,
+    formatter: Arc<dyn Formatter>,
+    show_thread: bool,
+    show_target: bool,
+    show_module_path: bool,
+    show_location: bool,
+    color: ColorMode,
+    palette: Palette,
+    color_scope: ColorScope,
+    target: Target,
+    stderr_level: LevelFilter,
+    writer: Option<Sink>,
+    file: Option<PathBuf>,
+    append: bool,
+    rotation: Option<Rotation>,
+    file_color: bool,
+    tee: bool,
+    sinks: Vec<Sink>,
+}
+
+impl Default for SimpleLoggerBuilder {
+    fn default() -> Self {
+        SimpleLoggerBuilder {
+            filter: Filter::default(),
+            env: None,
+            strict: false,
+            parse_error: None,
+            prefix: true,
+            timestamp: false,
+            timestamp_format: TimestampFormat::Elapsed,
+            precision: Precision::Millis,
+            formatter: Arc::new(DefaultFormatter),
+            show_thread: false,
+            show_target: false,
+            show_module_path: false,
+            show_location: false,
+            color: ColorMode::Auto,
+            palette: Palette::default(),
+            color_scope: ColorScope::Line,
+            target: Target::Stdout,
+            stderr_level: LevelFilter::Error,
+            writer: None,
+            file: None,
+            append: true,
+            rotation: None,
+            file_color: false,
+            tee: false,
+            sinks: Vec::new(),
+        }
+    }
+}
+
+impl SimpleLoggerBuilder {
+    /// Create a new builder with the default options: log level `Error`, level prefix on,
+    /// no timestamp, colour on (when writing to a terminal) and output to STDOUT, with `Error`
+    /// level output to STDERR
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum log level (verbosity) that will be output, for all modules that don't
+    /// have their own level set using `module_level()` or `directives()`.
+    /// `LevelFilter::Off` disables all output.
+    pub fn level(mut self, level: LevelFilter) -> Self {
+        self.filter.set_level(level);
+        self
+    }
+
+    /// Set the maximum log level (verbosity) that will be output for `module` (a log target,
+    /// normally a module path such as "mycrate::net") and all the modules below it. The level
+    /// of the most specific module that matches a log target is used.
+    pub fn module_level(mut self, module: &str, level: LevelFilter) -> Self {
+        self.filter.set_module_level(module, level);
+        self
+    }
+
+    /// Set log levels from a comma separated list of directives, each of which is either a
+    /// level to use by default or a `module=level` pair, e.g.
+    /// `"warn,mycrate=debug,mycrate::net=trace"`. See `strict()` for how directives that cannot
+    /// be parsed are handled.
+    pub fn directives(mut self, directives: &str) -> Self {
+        if let Err(e) = self.filter.add_directives(directives) {
+            self.parse_error.get_or_insert(e);
+        }
+        self
+    }
+
+    /// Read log level directives (with the same syntax as `directives()`) from the environment
+    /// variable `name`, such as `RUST_LOG_ENV` or `SIMPLOG_LEVEL_ENV`, when the logger is built.
+    /// Levels set explicitly on the builder take precedence over those from the environment,
+    /// which in turn take precedence over the default log level.
+    pub fn env(mut self, name: &str) -> Self {
+        self.env = Some(name.to_string());
+        self
+    }
+
+    /// Set how verbosity directives (from `directives()` or the environment), or a timestamp or
+    /// log line pattern, that cannot be parsed are handled. By default they are ignored and a
+    /// single warning describing the first of them, and any log file that could not be opened, is
+    /// written to STDERR. In strict mode `try_build()` and `try_init()` instead return an
+    /// `Error::Parse` for it, while `build()` and `init()` still ignore them.
+    ///
+    /// The warning is written to STDERR rather than to the outputs of the logger, as it must be
+    /// seen whatever the log level (which is `Error` by default), and must not end up in machine
+    /// readable output such as JSON. It is not written if the log level is `LevelFilter::Off`.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Set whether each log line is prefixed with the level that produced it
+    pub fn prefix(mut self, prefix: bool) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    /// Set whether each log line is prefixed with a timestamp, by default the elapsed time since
+    /// the logger was built
+    pub fn timestamp(mut self, timestamp: bool) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Set the format of the timestamp prefixed to each log line, and enable timestamps.
+    /// A `TimestampFormat::Custom` pattern that cannot be parsed is handled as described for
+    /// `strict()`, using `TimestampFormat::Elapsed` instead when it is ignored.
+    pub fn timestamp_format(mut self, format: TimestampFormat) -> Self {
+        self.timestamp_format = format;
+        self.timestamp = true;
+        self
+    }
+
+    /// Set the precision of the fractional seconds in timestamps, the default is milliseconds
+    pub fn timestamp_precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Set the `Formatter` used to lay out each log line, the default is `DefaultFormatter`
+    pub fn formatter<F: Formatter + 'static>(mut self, formatter: F) -> Self {
+        self.formatter = Arc::new(formatter);
+        self
+    }
+
+    /// Write each log line as a JSON object using `JsonFormatter`, with UTC timestamps. Call
+    /// `timestamp_format()` after this to use a different timestamp format.
+    pub fn json(self) -> Self {
+        self.formatter(JsonFormatter).timestamp_format(TimestampFormat::Utc)
+    }
+
+    /// Write each log line in logfmt format using `LogfmtFormatter`, with UTC timestamps. Call
+    /// `timestamp_format()` after this to use a different timestamp format.
+    pub fn logfmt(self) -> Self {
+        self.formatter(LogfmtFormatter).timestamp_format(TimestampFormat::Utc)
+    }
+
+    /// Use a `PatternFormatter` with `pattern` to lay out each log line, replacing any formatter
+    /// set before it, as later calls to `formatter()`, `json()` or `logfmt()` replace it.
+    /// Timestamps are enabled if the pattern includes them. A pattern that cannot be parsed is
+    /// handled as described for `strict()`, using the formatter previously set instead when it
+    /// is ignored.
+    pub fn pattern(mut self, pattern: &str) -> Self {
+        match PatternFormatter::new(pattern) {
+            Ok(pattern) => {
+                self.timestamp |= pattern.has_timestamp();
+                self.formatter = Arc::new(pattern);
+            }
+            Err(e) => {
+                self.parse_error.get_or_insert(e);
+            }
+        }
+        self
+    }
+
+    /// Set whether each log line shows the name of the thread that wrote it (or its id if it has
+    /// no name) after the timestamp, padded so that the lines of different threads line up and
+    /// coloured consistently per thread when the output is coloured. Only used by
+    /// `DefaultFormatter`.
+    pub fn show_thread(mut self, show: bool) -> Self {
+        self.show_thread = show;
+        self
+    }
+
+    /// Set whether each log line shows the target of the record, after the level. The target is
+    /// the module path of the log statement unless set using `target:` in the log macro. Only
+    /// used by `DefaultFormatter`.
+    pub fn show_target(mut self, show: bool) -> Self {
+        self.show_target = show;
+        self
+    }
+
+    /// Set whether each log line shows the module path of the log statement, after the target.
+    /// Only used by `DefaultFormatter`.
+    pub fn show_module_path(mut self, show: bool) -> Self {
+        self.show_module_path = show;
+        self
+    }
+
+    /// Set whether each log line shows the file and line of the log statement as "file:line",
+    /// with the file relative to the root of its crate, e.g. "src/net.rs:42". The source columns
+    /// shown are padded so that the messages line up. Only used by `DefaultFormatter`.
+    pub fn show_location(mut self, show: bool) -> Self {
+        self.show_location = show;
+        self
+    }
+
+    /// Set whether log lines are coloured by level when the output is a terminal, the same as
+    /// `color_mode(ColorMode::Auto)` if true and `color_mode(ColorMode::Never)` if false
+    pub fn color(self, color: bool) -> Self {
+        self.color_mode(if color { ColorMode::Auto } else { ColorMode::Never })
+    }
+
+    /// Set when log lines written to the console are coloured by level. The default is
+    /// `ColorMode::Auto`, which colours them when the output is a terminal, unless the
+    /// environment variables described in `ColorMode::Auto` say otherwise.
+    pub fn color_mode(mut self, mode: ColorMode) -> Self {
+        self.color = mode;
+        self
+    }
+
+    /// Set the colours used for each level, by default `Palette::classic()`
+    pub fn palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    /// Set whether the whole log line (the default) or only its level tag is coloured
+    pub fn color_scope(mut self, scope: ColorScope) -> Self {
+        self.color_scope = scope;
+        self
+    }
+
+    /// Set the output stream log lines are written to
+    pub fn target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Set the least severe level that is written to STDERR instead of STDOUT when the target
+    /// is `Target::Stdout`. The default is `LevelFilter::Error`, use `LevelFilter::Warn` to also
+    /// send warnings to STDERR, or `LevelFilter::Off` to send all output to STDOUT.
+    pub fn stderr_level(mut self, stderr_level: LevelFilter) -> Self {
+        self.stderr_level = stderr_level;
+        self
+    }
+
+    /// Write log output to `writer`, such as a socket, pipe or in-memory buffer, instead of
+    /// STDOUT and STDERR, in which case `target()` and `stderr_level()` are not used. Any
+    /// `Write + Send + 'static` can be used, including a `Box<dyn Write + Send>`. Each line is
+    /// written with a single call to `write_all()` while holding a lock.
+    /// It is coloured only if `color_mode()` is `ColorMode::Always`, or `ColorMode::Auto` and
+    /// `CLICOLOR_FORCE` is set, as it is not known to be a terminal.
+    pub fn writer<W: Write + Send + 'static>(mut self, writer: W) -> Self {
+        self.writer = Some(Sink::writer(writer));
+        self
+    }
+
+    /// Write log output to the file at `path` instead of the console. The file is created if it
+    /// doesn't exist, and is not coloured unless `file_color()` is used.
+    pub fn file<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.file = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Set whether log output is appended to the log file if it already exists (the default), or
+    /// the file is truncated when the logger is built
+    pub fn append(mut self, append: bool) -> Self {
+        self.append = append;
+        self
+    }
+
+    /// Set whether lines written to the log file are coloured by level using ANSI escape codes,
+    /// for viewing with a pager such as `less -R`. The default is false.
+    pub fn file_color(mut self, color: bool) -> Self {
+        self.file_color = color;
+        self
+    }
+
+    /// Set the policy used to rotate the log file
+    pub fn rotate(mut self, rotation: Rotation) -> Self {
+        self.rotation = Some(rotation);
+        self
+    }
+
+    /// Set whether log output is also written to the console (as set by `target()`, or the writer
+    /// set by `writer()`) when it is being written to a file
+    pub fn tee(mut self, tee: bool) -> Self {
+        self.tee = tee;
+        self
+    }
+
+    /// Add a `Sink` that log lines are written to, with its own log level, formatter and colour
+    /// mode. When any sinks are added they are used instead of the output set by `target()`,
+    /// `file()` or `writer()`.
+    pub fn sink(mut self, sink: Sink) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    /// Build a `SimpleLogger` with the options set on this builder. Verbosity directives that
+    /// cannot be parsed are ignored with a warning, even in strict mode. If the log file (or the
+    /// file of a sink) cannot be opened, it is not used and a warning is written. If no sink
+    /// could be opened output is written to the console instead.
+    pub fn build(self) -> SimpleLogger {
+        self.build_with(|name| env::var(name).ok())
+    }
+
+    /// Build a `SimpleLogger` with the options set on this builder, returning an `Error::Io` if
+    /// the log file cannot be opened, or an `Error::Parse` if in strict mode and a verbosity
+    /// directive could not be parsed
+    pub fn try_build(self) -> Result<SimpleLogger, Error> {
+        self.try_build_with(|name| env::var(name).ok())
+    }
+
+    // Build the logger as for `build()`, using `var` to read environment variables
+    fn build_with<V: Fn(&str) -> Option<String>>(self, var: V) -> SimpleLogger {
+        let mut errors = Vec::new();
+        let logger = self.build_logger(var, |e| -> Result<(), Infallible> {
+            errors.push(e);
+            Ok(())
+        });
+        let logger = match logger {
+            Ok(logger) => logger,
+            Err(never) => match never {},
+        };
+        Self::warn(&logger, &errors);
+        logger
+    }
+
+    // Build the logger as for `try_build()`, using `var` to read environment variables
+    fn try_build_with<V: Fn(&str) -> Option<String>>(self, var: V) -> Result<SimpleLogger, Error> {
+        let strict = self.strict;
+        let mut errors = Vec::new();
+        let logger = self.build_logger(var, |e| match e {
+            Error::Parse(_) if !strict => {
+                errors.push(e);
+                Ok(())
+            }
+            e => Err(e),
+        })?;
+        Self::warn(&logger, &errors);
+        Ok(logger)
+    }
+
+    // Write a single warning describing the errors ignored while building `logger`, if any
+    fn warn(logger: &SimpleLogger, errors: &[Error]) {
+        if !errors.is_empty() {
+            let errors: Vec<_> = errors.iter().map(Error::to_string).collect();
+            logger.warn(format_args!("{}", errors.join("; ")));
+        }
+    }
+
+    // Build the logger, using `var` to read environment variables. Each error is passed to
+    // `ignore`, which either returns `Ok` to build the logger without what caused it, or an error
+    // to stop building. At most one `Error::Parse` is passed, before any log file is opened.
+    fn build_logger<V, I, E>(self, var: V, mut ignore: I) -> Result<SimpleLogger, E>
+        where V: Fn(&str) -> Option<String>, I: FnMut(Error) -> Result<(), E> {
+        let mut filter = Filter::default();
+        let mut parse_error = None;
+        if let Some(spec) = self.env.as_deref().and_then(var) {
+            parse_error = filter.add_directives(&spec).err();
+        }
+        filter.merge(&self.filter);
+
+        let (clock, clock_error) = match Clock::new(self.timestamp_format.clone(), self.precision) {
+            Ok(clock) => (clock, None),
+            Err(e) => (Clock::elapsed(self.precision), Some(e)),
+        };
+
+        let mut sinks = self.sinks.clone();
+        let console = match self.target {
+            Target::Stdout => Sink::console(self.stderr_level),
+            Target::Stderr => Sink::stderr(),
+        };
+        let console = self.writer.clone().unwrap_or(console).color_mode(self.color);
+        if sinks.is_empty() {
+            if let Some(path) = &self.file {
+                let color = if self.file_color { ColorMode::Always } else { ColorMode::Never };
+                let file = Sink::file(path).append(self.append).color_mode(color);
+                sinks.push(match &self.rotation {
+                    Some(rotation) => file.rotate(rotation.clone()),
+                    None => file,
+                });
+            }
+            if self.file.is_none() || self.tee {
+                sinks.push(console.clone());
+            }
+        }
+
+        let mut sink_clock_error = None;
+        let clocks: Vec<_> = sinks.iter()
+            .map(|sink| sink.clock(self.precision).unwrap_or_else(|e| {
+                sink_clock_error.get_or_insert(e);
+                None
+            }))
+            .collect();
+
+        let parse_error = self.parse_error.clone().or(parse_error).or(clock_error)
+            .or(sink_clock_error);
+        if let Some(e) = parse_error {
+            ignore(e.into())?;
+        }
+
+        let mut outputs = Vec::new();
+        for (sink, clock) in sinks.iter().zip(clocks) {
+            match sink.open(&self.formatter, clock) {
+                Ok(output) => outputs.push(output),
+                Err(e) => ignore(e.into())?,
+            }
+        }
+        if outputs.is_empty() {
+            match console.open(&self.formatter, None) {
+                Ok(output) => outputs.push(output),
+                Err(e) => ignore(e.into())?,
+            }
+        }
+        let sink_level = outputs.iter()
+            .filter_map(|output| output.level)
+            .max()
+            .unwrap_or(LevelFilter::Off);
+
+        let logger = SimpleLogger {
+            settings: Arc::new(Settings::new(filter.level(), self.prefix, self.timestamp,
+                                             sink_level)),
+            filter: Arc::new(filter),
+            clock,
+            source: Arc::new(Source::new(self.show_thread, self.show_target, self.show_module_path,
+                                         self.show_location)),
+            palette: self.palette,
+            color_scope: self.color_scope,
+            sinks: outputs,
+        };
+        Ok(logger)
+    }
+
+    /// Build the `SimpleLogger` and install it as the logger used by the `log` framework,
+    /// returning a `LoggerHandle` to it. If the logger could not be installed (see `try_init()`)
+    /// the handle has no effect on the `log` framework's maximum log level.
+    pub fn init(self) -> LoggerHandle {
+        let logger = self.build();
+        let handle = logger.handle();
+        let _ = Self::install(logger);
+        handle
+    }
+
+    /// Build the `SimpleLogger` and install it as the logger used by the `log` framework,
+    /// returning an error if a logger has already been installed, if the log file cannot be opened
+    /// or if in strict mode and a verbosity directive could not be parsed. On error the `log`
+    /// framework's maximum log level is left unchanged.
+    pub fn try_init(self) -> Result<LoggerHandle, Error> {
+        let logger = self.try_build()?;
+        let handle = logger.handle();
+        Self::install(logger)?;
+        Ok(handle)
+    }
+
+    fn install(logger: SimpleLogger) -> Result<(), Error> {
+        let settings = logger.settings.clone();
+        let max_level = settings.max_level(&logger.filter);
+        log::set_boxed_logger(Box::new(logger))?;
+        log::set_max_level(max_level);
+        settings.set_installed();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use log::{LevelFilter, Log};
+    use termcolor::WriteColor;
+
+    use crate::writer::test::Shared;
+
+    use super::{ColorMode, SimpleLoggerBuilder, Sink, Target, TimestampFormat};
+
+    #[test]
+    fn default_options() {
+        let logger = SimpleLoggerBuilder::new().build();
+        assert_eq!(logger.settings.level(), crate::DEFAULT_LOG_LEVEL);
+        assert!(logger.settings.prefix());
+        assert!(!logger.settings.timestamp());
+        assert_eq!(logger.sinks.len(), 1);
+        assert!(logger.sinks[0].is_console());
+        assert!(logger.sinks[0].use_stderr(log::Level::Error));
+        assert!(!logger.sinks[0].use_stderr(log::Level::Warn));
+    }
+
+    #[test]
+    fn chained_options() {
+        let logger = SimpleLoggerBuilder::new()
+            .level(LevelFilter::Trace)
+            .module_level("hyper", LevelFilter::Warn)
+            .prefix(false)
+            .timestamp(true)
+            .color(false)
+            .target(Target::Stderr)
+            .stderr_level(LevelFilter::Warn)
+            .build();
+        assert_eq!(logger.settings.level(), LevelFilter::Trace);
+        assert!(!logger.settings.prefix());
+        assert!(logger.settings.timestamp());
+        assert!(!logger.sinks[0].buffer(log::Level::Info).supports_color());
+        assert!(!logger.sinks[0].buffer(log::Level::Error).supports_color());
+        assert!(logger.sinks[0].use_stderr(log::Level::Trace));
+        assert_eq!(logger.filter.level_for("hyper::client"), LevelFilter::Warn);
+    }
+
+    #[test]
+    fn color_mode() {
+        let logger = SimpleLoggerBuilder::new().color_mode(ColorMode::Always).build();
+        assert!(logger.sinks[0].buffer(log::Level::Info).supports_color());
+        assert!(logger.sinks[0].buffer(log::Level::Error).supports_color());
+    }
+
+    // Build a logger from `builder` with `vars` as the environment, so that tests don't need to
+    // change the environment of the process while other tests read it
+    fn build_with_env(builder: SimpleLoggerBuilder, vars: &[(&str, &str)], lenient: bool)
+        -> Result<crate::SimpleLogger, crate::Error> {
+        let var = |name: &str| {
+            vars.iter().find(|(var, _)| *var == name).map(|(_, value)| value.to_string())
+        };
+        match lenient {
+            true => Ok(builder.build_with(var)),
+            false => builder.try_build_with(var),
+        }
+    }
+
+    #[test]
+    fn env_level() {
+        let builder = SimpleLoggerBuilder::new().env("SIMPLOG_LEVEL");
+        let vars = [("SIMPLOG_LEVEL", "info,mycrate=debug")];
+        let logger = build_with_env(builder, &vars, true).unwrap();
+        assert_eq!(logger.settings.level(), LevelFilter::Info);
+        assert_eq!(logger.filter.level_for("mycrate"), LevelFilter::Debug);
+    }
+
+    #[test]
+    fn explicit_level_overrides_env() {
+        let builder = SimpleLoggerBuilder::new().level(LevelFilter::Warn).env("RUST_LOG");
+        let logger = build_with_env(builder, &[("RUST_LOG", "info,mycrate=debug")], true).unwrap();
+        assert_eq!(logger.settings.level(), LevelFilter::Warn);
+        assert_eq!(logger.filter.level_for("mycrate"), LevelFilter::Debug);
+    }
+
+    #[test]
+    fn strict_invalid_verbosity() {
+        let result = SimpleLoggerBuilder::new().directives("degub").strict(true).try_build();
+        assert!(matches!(result, Err(crate::Error::Parse(_))));
+    }
+
+    #[test]
+    fn lenient_invalid_verbosity() {
+        let logger = SimpleLoggerBuilder::new().directives("info,degub").try_build().unwrap();
+        assert_eq!(logger.settings.level(), LevelFilter::Info);
+    }
+
+    #[test]
+    fn strict_invalid_env() {
+        let builder = SimpleLoggerBuilder::new().env("RUST_LOG").strict(true);
+        let result = build_with_env(builder, &[("RUST_LOG", "mycrate=degub")], false);
+        assert!(matches!(result, Err(crate::Error::Parse(_))));
+    }
+
+    // A path for the log file of test `name`, in a directory of its own that is emptied first
+    fn test_path(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join("simplog_builder_tests").join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("test.log")
+    }
+
+    // Log `message` at `Error`, which is enabled by default, with the target "simplog"
+    fn log_error(logger: &crate::SimpleLogger, message: &str) {
+        logger.log(&log::Record::builder()
+            .level(log::Level::Error)
+            .target("simplog")
+            .args(format_args!("{}", message))
+            .build());
+    }
+
+    #[test]
+    fn file_output() {
+        let path = test_path("file_output");
+        let logger = SimpleLoggerBuilder::new().file(&path).build();
+        assert!(!logger.sinks.iter().any(|sink| sink.is_console()));
+        log_error(&logger, "Hello File");
+        logger.flush();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents, "ERROR\t- Hello File\n");
+    }
+
+    #[test]
+    fn custom_formatter() {
+        struct Upper;
+        impl crate::Formatter for Upper {
+            fn format(&self, out: &mut dyn termcolor::WriteColor, record: &log::Record,
+                      _context: &crate::Context) -> std::io::Result<()> {
+                write!(out, "{}", record.args().to_string().to_uppercase())
+            }
+        }
+
+        let path = test_path("custom_formatter");
+        let logger = SimpleLoggerBuilder::new().file(&path).append(false).formatter(Upper).build();
+        log_error(&logger, "Hello File");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "HELLO FILE\n");
+    }
+
+    #[test]
+    fn pattern() {
+        let path = test_path("pattern");
+        let logger = SimpleLoggerBuilder::new()
+            .file(&path)
+            .append(false)
+            .pattern("{d:3}|{c}{l:5}{/c}|{t}|{m}")
+            .timestamp_format(TimestampFormat::CustomUtc("%Y".into()))
+            .build();
+        assert!(logger.settings.timestamp());
+        log_error(&logger, "Hello File");
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.ends_with("|ERROR|simplog|Hello File\n"));
+
+        let result = SimpleLoggerBuilder::new().pattern("{nonsense}").strict(true).try_build();
+        assert!(matches!(result, Err(crate::Error::Parse(_))));
+    }
+
+    #[test]
+    fn last_formatter_wins() {
+        let (json, pattern) = (Shared::default(), Shared::default());
+        let logger = SimpleLoggerBuilder::new()
+            .writer(json.clone())
+            .pattern("{l}|{m}")
+            .json()
+            .build();
+        logger.log(&record(log::Level::Error, "app"));
+        assert!(json.contents().starts_with("{\"timestamp\":\""));
+
+        let logger = SimpleLoggerBuilder::new()
+            .writer(pattern.clone())
+            .color_mode(ColorMode::Never)
+            .json()
+            .pattern("{l}|{m}")
+            .build();
+        logger.log(&record(log::Level::Error, "app"));
+        assert!(pattern.contents().ends_with("ERROR|Hello\n"));
+    }
+
+    #[test]
+    fn json() {
+        let path = test_path("json");
+        let logger = SimpleLoggerBuilder::new().file(&path).append(false).json().build();
+        log_error(&logger, "Hello \"File\"");
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("{\"timestamp\":\""));
+        assert!(contents.ends_with(",\"message\":\"Hello \\\"File\\\"\"}\n"));
+    }
+
+    #[test]
+    fn logfmt() {
+        let path = test_path("logfmt");
+        let logger = SimpleLoggerBuilder::new().file(&path).append(false).logfmt().build();
+        log_error(&logger, "Hello File");
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("ts="));
+        assert!(contents.ends_with(" level=error target=simplog msg=\"Hello File\"\n"));
+    }
+
+    #[test]
+    fn show_source() {
+        let path = test_path("show_source");
+        let logger = SimpleLoggerBuilder::new()
+            .file(&path)
+            .append(false)
+            .show_target(true)
+            .show_module_path(true)
+            .show_location(true)
+            .build();
+        logger.log(&log::Record::builder()
+            .level(log::Level::Error)
+            .target("app")
+            .module_path(Some("app::net"))
+            .file(Some("crates/app/src/net.rs"))
+            .line(Some(42))
+            .args(format_args!("Hello File"))
+            .build());
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents, "ERROR\t- app app::net src/net.rs:42 Hello File\n");
+    }
+
+    #[test]
+    fn file_color() {
+        let path = test_path("file_color");
+        let logger = SimpleLoggerBuilder::new().file(&path).append(false).file_color(true).build();
+        log_error(&logger, "Hello File");
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents, "\x1b[0m\x1b[31mERROR\t- Hello File\x1b[0m\n");
+    }
+
+    fn record(level: log::Level, target: &str) -> log::Record<'_> {
+        log::Record::builder().level(level).target(target).args(format_args!("Hello")).build()
+    }
+
+    #[test]
+    fn writer() {
+        let shared = Shared::default();
+        let logger = SimpleLoggerBuilder::new()
+            .writer(shared.clone())
+            .color_mode(ColorMode::Never)
+            .build();
+        log_error(&logger, "Hello Writer");
+        logger.log(&log::Record::builder().level(log::Level::Error).args(format_args!("Oops")).build());
+        logger.flush();
+        assert_eq!(shared.contents(), "ERROR\t- Hello Writer\nERROR\t- Oops\n");
+        assert!(shared.flushed());
+
+        let logger = SimpleLoggerBuilder::new()
+            .writer(shared.clone())
+            .color_mode(ColorMode::Always)
+            .build();
+        assert!(logger.sinks[0].buffer(log::Level::Info).supports_color());
+
+        let json = Shared::default();
+        let logger = SimpleLoggerBuilder::new()
+            .writer(json.clone())
+            .color_mode(ColorMode::Always)
+            .json()
+            .build();
+        logger.log(&record(log::Level::Error, "app"));
+        assert!(json.contents().ends_with(",\"message\":\"Hello\"}\n"));
+        assert!(!json.contents().contains('\x1b'));
+    }
+
+    #[test]
+    fn sinks() {
+        let (errors, json, trace) = (Shared::default(), Shared::default(), Shared::default());
+        let logger = SimpleLoggerBuilder::new()
+            .module_level("noisy", LevelFilter::Off)
+            .sink(Sink::writer(errors.clone()).level(LevelFilter::Error)
+                .color_mode(ColorMode::Always))
+            .sink(Sink::writer(json.clone()).level(LevelFilter::Info).json())
+            .sink(Sink::writer(trace.clone()).level(LevelFilter::Trace).color_mode(ColorMode::Never))
+            .build();
+        assert!(!logger.sinks.iter().any(|sink| sink.is_console()));
+        assert_eq!(logger.settings.max_level(&logger.filter), LevelFilter::Trace);
+
+        let metadata = |level| log::Metadata::builder().level(level).target("app").build();
+        assert!(logger.enabled(&metadata(log::Level::Trace)));
+        assert!(!logger.enabled(&log::Metadata::builder().level(log::Level::Error).target("noisy").build()));
+
+        logger.log(&record(log::Level::Error, "app"));
+        logger.log(&record(log::Level::Info, "app"));
+        logger.log(&record(log::Level::Trace, "app"));
+        logger.log(&record(log::Level::Error, "noisy"));
+        assert_eq!(errors.contents(), "\x1b[0m\x1b[31mERROR\t- Hello\x1b[0m\n");
+        assert_eq!(json.contents().lines().count(), 2);
+        assert!(json.contents().lines().all(|line| line.starts_with("{\"timestamp\":\"")));
+        assert_eq!(trace.contents(), "ERROR\t- Hello\nINFO\t- Hello\nTRACE\t- Hello\n");
+    }
+
+    #[test]
+    fn sink_uses_default_level() {
+        let shared = Shared::default();
+        let logger = SimpleLoggerBuilder::new()
+            .level(LevelFilter::Warn)
+            .sink(Sink::writer(shared.clone()).color_mode(ColorMode::Never))
+            .build();
+        logger.log(&record(log::Level::Info, "app"));
+        logger.handle().set_level(LevelFilter::Info);
+        logger.log(&record(log::Level::Info, "app"));
+        assert_eq!(shared.contents(), "INFO\t- Hello\n");
+    }
+
+    #[test]
+    fn sink_level_caps_module_level() {
+        let (errors, debug) = (Shared::default(), Shared::default());
+        let logger = SimpleLoggerBuilder::new()
+            .directives("mycrate=debug")
+            .sink(Sink::writer(errors.clone()).level(LevelFilter::Error)
+                .color_mode(ColorMode::Never))
+            .sink(Sink::writer(debug.clone()).color_mode(ColorMode::Never))
+            .build();
+        logger.log(&record(log::Level::Debug, "mycrate"));
+        logger.log(&record(log::Level::Error, "mycrate"));
+        assert_eq!(errors.contents(), "ERROR\t- Hello\n");
+        assert_eq!(debug.contents(), "DEBUG\t- Hello\nERROR\t- Hello\n");
+    }
+
+    #[test]
+    fn sink_timestamp_format() {
+        let (utc, default) = (Shared::default(), Shared::default());
+        let logger = SimpleLoggerBuilder::new()
+            .sink(Sink::writer(utc.clone()).color_mode(ColorMode::Never)
+                .timestamp_format(TimestampFormat::CustomUtc("%Y".into())))
+            .sink(Sink::writer(default.clone()).color_mode(ColorMode::Never))
+            .build();
+        logger.log(&record(log::Level::Error, "app"));
+        assert!(utc.contents().ends_with("ERROR\t- Hello\n"));
+        assert!(utc.contents().starts_with(|c: char| c.is_ascii_digit()));
+        assert_eq!(default.contents(), "ERROR\t- Hello\n");
+
+        let result = SimpleLoggerBuilder::new()
+            .sink(Sink::stderr().timestamp_format(TimestampFormat::Custom("%Q".into())))
+            .strict(true)
+            .try_build();
+        assert!(matches!(result, Err(crate::Error::Parse(_))));
+    }
+
+    #[test]
+    fn file_sink_is_not_colored() {
+        let path = test_path("file_sink_is_not_colored");
+        let sink = Sink::file(&path);
+        let output = sink.open(&SimpleLoggerBuilder::new().formatter, None).unwrap();
+        assert!(!output.buffer(log::Level::Error).supports_color());
+
+        let output = sink.color_mode(ColorMode::Always)
+            .open(&SimpleLoggerBuilder::new().formatter, None).unwrap();
+        assert!(output.buffer(log::Level::Error).supports_color());
+    }
+
+    #[test]
+    fn sink_open_error() {
+        let path = std::env::temp_dir().join("simplog_no_such_dir").join("sink.log");
+        let result = SimpleLoggerBuilder::new().sink(Sink::file(&path)).try_build();
+        assert!(matches!(result, Err(crate::Error::Io(_))));
+        let logger = SimpleLoggerBuilder::new().sink(Sink::file(&path)).build();
+        assert_eq!(logger.sinks.len(), 1);
+        assert!(logger.sinks[0].is_console());
+    }
+
+    #[test]
+    fn file_tee() {
+        let path = test_path("file_tee");
+        let logger = SimpleLoggerBuilder::new().file(&path).tee(true).build();
+        assert_eq!(logger.sinks.len(), 2);
+        assert!(!logger.sinks[0].is_console());
+        assert!(logger.sinks[1].is_console());
+    }
+
+    #[test]
+    fn file_truncate() {
+        let path = test_path("file_truncate");
+        std::fs::write(&path, "old contents\n").unwrap();
+        let logger = SimpleLoggerBuilder::new().file(&path).append(false).build();
+        log_error(&logger, "new contents");
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents, "ERROR\t- new contents\n");
+    }
+
+    #[test]
+    fn file_open_error() {
+        let path = std::env::temp_dir().join("simplog_no_such_dir").join("simplog.log");
+        let result = SimpleLoggerBuilder::new().file(&path).try_build();
+        assert!(matches!(result, Err(crate::Error::Io(_))));
+        let logger = SimpleLoggerBuilder::new().file(&path).build();
+        assert_eq!(logger.sinks.len(), 1);
+        assert!(logger.sinks[0].is_console());
+    }
+
+    #[test]
+    fn timestamp_format() {
+        let logger = SimpleLoggerBuilder::new()
+            .timestamp_format(TimestampFormat::CustomUtc("%Y".into()))
+            .build();
+        assert!(logger.settings.timestamp());
+        let result = SimpleLoggerBuilder::new()
+            .timestamp_format(TimestampFormat::Custom("%Q".into()))
+            .strict(true)
+            .try_build();
+        assert!(matches!(result, Err(crate::Error::Parse(_))));
+    }
+
+    #[test]
+    fn missing_env_uses_default() {
+        let builder = SimpleLoggerBuilder::new().env("RUST_LOG");
+        let logger = build_with_env(builder, &[("SIMPLOG_LEVEL", "info")], true).unwrap();
+        assert_eq!(logger.settings.level(), crate::DEFAULT_LOG_LEVEL);
+    }
+}
@@ -0,0 +1,221 @@
+use std::env;
+
+use atty::Stream;
+use log::Level;
+use termcolor::{Color, ColorSpec};
+
+/// When log lines written to the console are coloured
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Colour the output if the stream being written to is a terminal, unless the environment
+    /// says otherwise: `NO_COLOR` (set and not empty), `CLICOLOR=0` or `TERM=dumb` turn colour
+    /// off, and `CLICOLOR_FORCE` (set and not "0") turns it on even when the output is not a
+    /// terminal. `NO_COLOR` takes precedence over `CLICOLOR_FORCE`.
+    #[default]
+    Auto,
+    /// Always colour the output
+    Always,
+    /// Never colour the output
+    Never,
+}
+
+impl ColorMode {
+    // Determine if output written to `stream` should be coloured
+    pub(crate) fn enabled(self, stream: Stream) -> bool {
+        self.resolve(|name| env::var(name).ok(), || atty::is(stream))
+    }
+
+    // Determine if output written to a writer, which is not known to be a terminal, should be
+    // coloured
+    pub(crate) fn enabled_for_writer(self) -> bool {
+        self.resolve(|name| env::var(name).ok(), || false)
+    }
+
+    // Determine if output should be coloured, using `var` to read environment variables and
+    // `is_tty` to check if the stream is a terminal
+    fn resolve<V, T>(self, var: V, is_tty: T) -> bool
+        where V: Fn(&str) -> Option<String>, T: FnOnce() -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => {
+                let set = |name| var(name).is_some_and(|value| !value.is_empty() && value != "0");
+                if var("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+                    false
+                } else if set("CLICOLOR_FORCE") {
+                    true
+                } else if var("CLICOLOR").as_deref() == Some("0") ||
+                    var("TERM").as_deref() == Some("dumb") {
+                    false
+                } else {
+                    is_tty()
+                }
+            }
+        }
+    }
+}
+
+/// Which part of a log line is coloured with the colour for its level
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorScope {
+    /// Colour the whole log line
+    #[default]
+    Line,
+    /// Colour only the level tag of the log line, where the `Formatter` writes it
+    Level,
+}
+
+// Create a `ColorSpec` with foreground colour `color`, that is bold and/or intense
+fn spec(color: Color, bold: bool, intense: bool) -> ColorSpec {
+    let mut spec = ColorSpec::new();
+    spec.set_fg(Some(color)).set_bold(bold).set_intense(intense);
+    spec
+}
+
+/// The colours (and styles such as bold or underline) used for the log lines of each level
+///
+/// # Example
+/// ```
+/// use log::Level;
+/// use simplog::{Palette, SimpleLogger};
+/// use termcolor::{Color, ColorSpec};
+///
+/// let mut debug = ColorSpec::new();
+/// debug.set_fg(Some(Color::Cyan)).set_underline(true);
+/// SimpleLogger::builder().palette(Palette::dark().set(Level::Debug, debug)).init();
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Palette {
+    error: ColorSpec,
+    warn: ColorSpec,
+    info: ColorSpec,
+    debug: ColorSpec,
+    trace: ColorSpec,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::classic()
+    }
+}
+
+impl Palette {
+    /// The palette used by default: Error is red, Warn yellow, Info magenta, Debug blue and
+    /// Trace green
+    pub fn classic() -> Self {
+        Palette {
+            error: spec(Color::Red, false, false),
+            warn: spec(Color::Yellow, false, false),
+            info: spec(Color::Magenta, false, false),
+            debug: spec(Color::Blue, false, false),
+            trace: spec(Color::Green, false, false),
+        }
+    }
+
+    /// A palette of bright colours that are readable on dark backgrounds: Error is bold red, Warn
+    /// yellow, Info green, Debug cyan and Trace white
+    pub fn dark() -> Self {
+        Palette {
+            error: spec(Color::Red, true, true),
+            warn: spec(Color::Yellow, false, true),
+            info: spec(Color::Green, false, true),
+            debug: spec(Color::Cyan, false, true),
+            trace: spec(Color::White, false, false),
+        }
+    }
+
+    /// A palette of darker colours that are readable on light backgrounds: Error is bold red,
+    /// Warn magenta, Info blue, Debug cyan and Trace black
+    pub fn light() -> Self {
+        Palette {
+            error: spec(Color::Red, true, false),
+            warn: spec(Color::Magenta, false, false),
+            info: spec(Color::Blue, false, false),
+            debug: spec(Color::Cyan, false, false),
+            trace: spec(Color::Black, false, false),
+        }
+    }
+
+    /// Set the colour and style used for `level`
+    pub fn set(mut self, level: Level, color: ColorSpec) -> Self {
+        *self.level_mut(level) = color;
+        self
+    }
+
+    /// Get the colour and style used for `level`
+    pub fn get(&self, level: Level) -> &ColorSpec {
+        match level {
+            Level::Error => &self.error,
+            Level::Warn => &self.warn,
+            Level::Info => &self.info,
+            Level::Debug => &self.debug,
+            Level::Trace => &self.trace,
+        }
+    }
+
+    fn level_mut(&mut self, level: Level) -> &mut ColorSpec {
+        match level {
+            Level::Error => &mut self.error,
+            Level::Warn => &mut self.warn,
+            Level::Info => &mut self.info,
+            Level::Debug => &mut self.debug,
+            Level::Trace => &mut self.trace,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use log::Level;
+    use termcolor::{Color, ColorSpec};
+
+    use super::{ColorMode, Palette};
+
+    #[test]
+    fn classic_palette() {
+        let palette = Palette::default();
+        assert_eq!(palette.get(Level::Error).fg(), Some(&Color::Red));
+        assert_eq!(palette.get(Level::Info).fg(), Some(&Color::Magenta));
+        assert_eq!(palette.get(Level::Trace).fg(), Some(&Color::Green));
+    }
+
+    #[test]
+    fn set_palette_color() {
+        let mut debug = ColorSpec::new();
+        debug.set_fg(Some(Color::Cyan)).set_bg(Some(Color::Black)).set_underline(true);
+        let palette = Palette::dark().set(Level::Debug, debug.clone());
+        assert_eq!(palette.get(Level::Debug), &debug);
+        assert_eq!(palette.get(Level::Error), Palette::dark().get(Level::Error));
+        assert!(palette.get(Level::Error).bold());
+    }
+
+    fn resolve(mode: ColorMode, vars: &[(&str, &str)], is_tty: bool) -> bool {
+        mode.resolve(|name| vars.iter().find(|(var, _)| *var == name).map(|(_, value)| value.to_string()),
+                     || is_tty)
+    }
+
+    #[test]
+    fn explicit_modes() {
+        assert!(resolve(ColorMode::Always, &[("NO_COLOR", "1")], false));
+        assert!(!resolve(ColorMode::Never, &[("CLICOLOR_FORCE", "1")], true));
+    }
+
+    #[test]
+    fn auto_uses_tty() {
+        assert!(resolve(ColorMode::Auto, &[], true));
+        assert!(!resolve(ColorMode::Auto, &[], false));
+        assert!(resolve(ColorMode::Auto, &[("CLICOLOR", "1"), ("TERM", "xterm")], true));
+    }
+
+    #[test]
+    fn auto_env() {
+        assert!(!resolve(ColorMode::Auto, &[("NO_COLOR", "1")], true));
+        assert!(resolve(ColorMode::Auto, &[("NO_COLOR", "")], true));
+        assert!(!resolve(ColorMode::Auto, &[("CLICOLOR", "0")], true));
+        assert!(!resolve(ColorMode::Auto, &[("TERM", "dumb")], true));
+        assert!(resolve(ColorMode::Auto, &[("CLICOLOR_FORCE", "1")], false));
+        assert!(resolve(ColorMode::Auto, &[("CLICOLOR_FORCE", "1"), ("TERM", "dumb")], false));
+        assert!(!resolve(ColorMode::Auto, &[("CLICOLOR_FORCE", "0")], false));
+        assert!(!resolve(ColorMode::Auto, &[("CLICOLOR_FORCE", "1"), ("NO_COLOR", "1")], true));
+    }
+}
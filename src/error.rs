@@ -0,0 +1,109 @@
+use std::fmt;
+use std::io;
+
+use log::SetLoggerError;
+
+const VALID_VERBOSITY: &str = "off, error, warn, info, debug, trace, or 0-5";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ParseErrorKind {
+    Verbosity,
+    Directive,
+    Timestamp,
+    Pattern,
+}
+
+/// The error returned when a verbosity string, a timestamp pattern or a log line pattern cannot
+/// be parsed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    input: String,
+    kind: ParseErrorKind,
+}
+
+impl ParseError {
+    pub(crate) fn verbosity(input: &str) -> Self {
+        ParseError { input: input.to_string(), kind: ParseErrorKind::Verbosity }
+    }
+
+    pub(crate) fn directive(input: &str) -> Self {
+        ParseError { input: input.to_string(), kind: ParseErrorKind::Directive }
+    }
+
+    pub(crate) fn timestamp(input: &str) -> Self {
+        ParseError { input: input.to_string(), kind: ParseErrorKind::Timestamp }
+    }
+
+    pub(crate) fn pattern(input: &str) -> Self {
+        ParseError { input: input.to_string(), kind: ParseErrorKind::Pattern }
+    }
+
+    /// The verbosity string, the directive within it, or the pattern that could not be parsed
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ParseErrorKind::Verbosity => write!(f, "invalid verbosity '{}', valid values are: {}",
+                self.input, VALID_VERBOSITY),
+            ParseErrorKind::Directive => write!(f, "invalid verbosity directive '{}', valid values \
+                are: {}, or a list of them as module=verbosity", self.input, VALID_VERBOSITY),
+            ParseErrorKind::Timestamp => write!(f, "invalid timestamp pattern '{}'", self.input),
+            ParseErrorKind::Pattern => write!(f, "invalid log line pattern '{}'", self.input),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// The errors that can be returned when initializing a `SimpleLogger`
+#[derive(Debug)]
+pub enum Error {
+    /// A logger has already been installed for the `log` framework
+    SetLogger(SetLoggerError),
+    /// The verbosity or a pattern could not be parsed, in strict mode
+    Parse(ParseError),
+    /// The log file could not be opened
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::SetLogger(e) => write!(f, "could not install logger: {}", e),
+            Error::Parse(e) => e.fmt(f),
+            Error::Io(e) => write!(f, "could not open log file: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::SetLogger(e) => Some(e),
+            Error::Parse(e) => Some(e),
+            Error::Io(e) => Some(e),
+        }
+    }
+}
+
+impl From<SetLoggerError> for Error {
+    fn from(e: SetLoggerError) -> Self {
+        Error::SetLogger(e)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        Error::Parse(e)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
@@ -0,0 +1,327 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How often a log file is rotated, in addition to any maximum size, based on UTC time
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Period {
+    /// Rotate the log file when the first line of a new hour is written
+    Hourly,
+    /// Rotate the log file when the first line of a new day is written
+    Daily,
+}
+
+impl Period {
+    fn seconds(&self) -> u64 {
+        match self {
+            Period::Hourly => 60 * 60,
+            Period::Daily => 24 * 60 * 60,
+        }
+    }
+}
+
+/// The policy for rotating a log file set with `SimpleLoggerBuilder::file()`. When the log
+/// file is rotated it is renamed with the suffix ".1", any previously rotated files are renamed
+/// to the next number up (".2", ".3" etc), and those beyond the number to keep are deleted.
+///
+/// # Example
+/// ```
+/// use simplog::{Period, Rotation, SimpleLogger};
+///
+/// SimpleLogger::builder()
+///     .file(std::env::temp_dir().join("rotation_example.log"))
+///     .rotate(Rotation::size(10 * 1024 * 1024).period(Period::Daily).keep(7))
+///     .init();
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rotation {
+    max_size: Option<u64>,
+    period: Option<Period>,
+    keep: usize,
+    #[cfg(feature = "gzip")]
+    compress: bool,
+}
+
+impl Default for Rotation {
+    fn default() -> Self {
+        Rotation {
+            max_size: None,
+            period: None,
+            keep: 5,
+            #[cfg(feature = "gzip")]
+            compress: false,
+        }
+    }
+}
+
+impl Rotation {
+    /// Rotate the log file before a line is written that would take it over `max_size` bytes
+    pub fn size(max_size: u64) -> Self {
+        Rotation::default().max_size(max_size)
+    }
+
+    /// Rotate the log file every `Period`
+    pub fn every(period: Period) -> Self {
+        Rotation::default().period(period)
+    }
+
+    /// Set the size in bytes that the log file is rotated before exceeding
+    pub fn max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Set the `Period` after which the log file is rotated
+    pub fn period(mut self, period: Period) -> Self {
+        self.period = Some(period);
+        self
+    }
+
+    /// Set the number of rotated log files that are kept, the default is 5
+    pub fn keep(mut self, keep: usize) -> Self {
+        self.keep = keep;
+        self
+    }
+
+    /// Set whether rotated log files are compressed with gzip, adding a ".gz" suffix
+    #[cfg(feature = "gzip")]
+    pub fn compress(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    fn suffix(&self) -> &'static str {
+        #[cfg(feature = "gzip")]
+        if self.compress {
+            return ".gz";
+        }
+        ""
+    }
+}
+
+// The number of the `Period` that `time` falls in, counting from the unix epoch
+fn period_number(period: Period, time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()) / period.seconds()
+}
+
+// The path of the `number`th rotated file of `path`, with `suffix` added
+fn rotated_path(path: &Path, number: usize, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{}{}", number, suffix));
+    PathBuf::from(name)
+}
+
+#[derive(Debug)]
+struct LogFile {
+    file: File,
+    path: PathBuf,
+    size: u64,
+    period: u64,
+    rotation: Option<Rotation>,
+}
+
+impl LogFile {
+    fn open(path: &Path, append: bool) -> io::Result<File> {
+        OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)
+    }
+
+    fn needs_rotation(&self, rotation: &Rotation, len: u64, now: SystemTime) -> bool {
+        let too_big = rotation.max_size
+            .is_some_and(|max_size| self.size > 0 && self.size + len > max_size);
+        let new_period = rotation.period
+            .is_some_and(|period| period_number(period, now) != self.period);
+        too_big || new_period
+    }
+
+    // Move the current log file and previously rotated ones along, and start a new log file
+    fn rotate(&mut self, rotation: &Rotation) -> io::Result<()> {
+        let suffix = rotation.suffix();
+        if rotation.keep > 0 {
+            let _ = fs::remove_file(rotated_path(&self.path, rotation.keep, suffix));
+            for number in (1..rotation.keep).rev() {
+                let from = rotated_path(&self.path, number, suffix);
+                if from.exists() {
+                    fs::rename(from, rotated_path(&self.path, number + 1, suffix))?;
+                }
+            }
+            fs::rename(&self.path, rotated_path(&self.path, 1, ""))?;
+            #[cfg(feature = "gzip")]
+            if rotation.compress {
+                compress(&rotated_path(&self.path, 1, ""))?;
+            }
+        }
+        self.file = Self::open(&self.path, false)?;
+        self.size = 0;
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &[u8], now: SystemTime) {
+        if let Some(rotation) = self.rotation.take() {
+            if self.needs_rotation(&rotation, line.len() as u64, now) {
+                // If rotation fails keep writing to the current file, rather than lose output
+                let _ = self.rotate(&rotation);
+            }
+            if let Some(period) = rotation.period {
+                self.period = period_number(period, now);
+            }
+            self.rotation = Some(rotation);
+        }
+
+        if self.file.write_all(line).is_ok() {
+            self.size += line.len() as u64;
+        }
+    }
+}
+
+// Compress the file at `path` with gzip, to the same path with ".gz" added, and remove it
+#[cfg(feature = "gzip")]
+fn compress(path: &Path) -> io::Result<()> {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    let mut compressed = path.as_os_str().to_os_string();
+    compressed.push(".gz");
+    let mut input = File::open(path)?;
+    let mut encoder = GzEncoder::new(File::create(compressed)?, Compression::default());
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?;
+    fs::remove_file(path)
+}
+
+/*
+    A file that log lines are written to, optionally rotated. Each line is written with a single
+    call while holding the lock, so lines written from different threads are never interleaved,
+    and rotation happens between lines.
+*/
+#[derive(Debug)]
+pub(crate) struct FileSink {
+    file: Mutex<LogFile>,
+}
+
+impl FileSink {
+    // Open the file at `path`, creating it if needed and either appending to it or truncating it
+    pub(crate) fn open(path: &Path, append: bool, rotation: Option<Rotation>) -> io::Result<Self> {
+        let file = LogFile::open(path, append)?;
+        let metadata = file.metadata()?;
+        let modified = metadata.modified().unwrap_or_else(|_| SystemTime::now());
+        let period = rotation.as_ref()
+            .and_then(|rotation| rotation.period)
+            .map_or(0, |period| period_number(period, modified));
+        Ok(FileSink {
+            file: Mutex::new(LogFile {
+                file,
+                path: path.to_path_buf(),
+                size: metadata.len(),
+                period,
+                rotation,
+            })
+        })
+    }
+
+    pub(crate) fn write_line(&self, line: &[u8]) {
+        if let Ok(mut file) = self.file.lock() {
+            file.write_line(line, SystemTime::now());
+        }
+    }
+
+    pub(crate) fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.file.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{rotated_path, FileSink, Period, Rotation};
+
+    fn test_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join("simplog_rotation_tests").join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("test.log")
+    }
+
+    #[test]
+    fn rotate_on_size() {
+        let path = test_path("size");
+        let sink = FileSink::open(&path, true, Some(Rotation::size(10).keep(2))).unwrap();
+        sink.write_line(b"line 1\n");
+        sink.write_line(b"line 2\n");
+        sink.write_line(b"line 3\n");
+        sink.write_line(b"line 4\n");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "line 4\n");
+        assert_eq!(fs::read_to_string(rotated_path(&path, 1, "")).unwrap(), "line 3\n");
+        assert_eq!(fs::read_to_string(rotated_path(&path, 2, "")).unwrap(), "line 2\n");
+        assert!(!rotated_path(&path, 3, "").exists());
+    }
+
+    #[test]
+    fn rotate_hourly() {
+        let path = test_path("hourly");
+        let sink = FileSink::open(&path, true, Some(Rotation::every(Period::Hourly))).unwrap();
+        let hour = UNIX_EPOCH + Duration::from_secs(1_000 * 3600);
+        let mut file = sink.file.lock().unwrap();
+        file.write_line(b"line 1\n", hour);
+        file.write_line(b"line 2\n", hour + Duration::from_secs(3599));
+        file.write_line(b"line 3\n", hour + Duration::from_secs(3600));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "line 3\n");
+        assert_eq!(fs::read_to_string(rotated_path(&path, 1, "")).unwrap(), "line 1\nline 2\n");
+    }
+
+    #[test]
+    fn keep_none() {
+        let path = test_path("keep_none");
+        let sink = FileSink::open(&path, true, Some(Rotation::size(10).keep(0))).unwrap();
+        sink.write_line(b"line 1\n");
+        sink.write_line(b"line 2\n");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "line 2\n");
+        assert!(!rotated_path(&path, 1, "").exists());
+    }
+
+    #[test]
+    fn concurrent_rotation() {
+        let path = test_path("concurrent");
+        let sink = std::sync::Arc::new(
+            FileSink::open(&path, true, Some(Rotation::size(100).keep(100))).unwrap());
+        let threads: Vec<_> = (0..4).map(|_| {
+            let sink = sink.clone();
+            std::thread::spawn(move || for _ in 0..50 { sink.write_line(b"0123456789\n") })
+        }).collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        let mut total = fs::read_to_string(&path).unwrap();
+        for number in 1..=100 {
+            if let Ok(contents) = fs::read_to_string(rotated_path(&path, number, "")) {
+                total.push_str(&contents);
+            }
+        }
+        assert_eq!(total.len(), 4 * 50 * 11);
+        assert!(total.lines().all(|line| line == "0123456789"));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn compress_rotated() {
+        let path = test_path("compress");
+        let sink = FileSink::open(&path, true, Some(Rotation::size(10).compress(true))).unwrap();
+        sink.write_line(b"line 1\n");
+        sink.write_line(b"line 2\n");
+        sink.write_line(b"line 3\n");
+        assert!(rotated_path(&path, 1, ".gz").exists());
+        assert!(rotated_path(&path, 2, ".gz").exists());
+        assert!(!rotated_path(&path, 1, "").exists());
+    }
+}
@@ -0,0 +1,220 @@
+use std::str::FromStr;
+
+use log::LevelFilter;
+
+use crate::{ParseError, DEFAULT_LOG_LEVEL};
+
+/// Parse a verbosity string ("off", "error", "warn", "info", "debug" or "trace", in any case, or
+/// the numbers 0 to 5 for the same levels) into the `LevelFilter` it corresponds to, returning a
+/// `ParseError` that describes the valid values if it is not valid.
+///
+/// # Example
+/// ```
+/// use log::LevelFilter;
+/// use simplog::parse_verbosity;
+///
+/// assert_eq!(parse_verbosity("Debug"), Ok(LevelFilter::Debug));
+/// let error = parse_verbosity("degub").unwrap_err();
+/// assert_eq!(error.to_string(),
+///     "invalid verbosity 'degub', valid values are: off, error, warn, info, debug, trace, or 0-5");
+/// ```
+pub fn parse_verbosity(verbosity: &str) -> Result<LevelFilter, ParseError> {
+    let verbosity = verbosity.trim();
+    match verbosity.parse::<usize>() {
+        Ok(number) => LevelFilter::iter().nth(number),
+        Err(_) => LevelFilter::from_str(verbosity).ok(),
+    }.ok_or_else(|| ParseError::verbosity(verbosity))
+}
+
+/*
+    A filter that determines the log level for a record from its target, using an optional
+    log level per module (target prefix) and a default level for all other targets.
+    If no default level has been set then DEFAULT_LOG_LEVEL is used.
+    Module levels are kept sorted longest name first, so the first match is the most specific.
+*/
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Filter {
+    default: Option<LevelFilter>,
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl Filter {
+    // The level used for targets that don't match any module
+    pub(crate) fn level(&self) -> LevelFilter {
+        self.default.unwrap_or(DEFAULT_LOG_LEVEL)
+    }
+
+    pub(crate) fn set_level(&mut self, level: LevelFilter) {
+        self.default = Some(level);
+    }
+
+    // Set the level for `module` and all modules below it, replacing any previous level for it
+    pub(crate) fn set_module_level(&mut self, module: &str, level: LevelFilter) {
+        match self.modules.iter_mut().find(|(name, _)| name == module) {
+            Some(entry) => entry.1 = level,
+            None => {
+                self.modules.push((module.to_string(), level));
+                self.modules.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+            }
+        }
+    }
+
+    /*
+        Add the directives in `spec` to the filter. `spec` is a comma separated list of directives,
+        each of which is either a log level as accepted by `parse_verbosity` that is
+        used by default, or a "module=level" pair that sets the level for that module and those
+        below it, e.g. "warn,mycrate=debug,mycrate::net=trace"
+        All valid directives are applied, and an error is returned for the first invalid one.
+    */
+    pub(crate) fn add_directives(&mut self, spec: &str) -> Result<(), ParseError> {
+        let mut result = Ok(());
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            let parsed = match directive.split_once('=') {
+                None => parse_verbosity(directive).map(|level| self.set_level(level)),
+                Some((module, _)) if module.trim().is_empty() => Err(ParseError::directive(directive)),
+                Some((module, level)) => parse_verbosity(level)
+                    .map(|level| self.set_module_level(module.trim(), level))
+                    .map_err(|_| ParseError::directive(directive)),
+            };
+            if result.is_ok() {
+                result = parsed;
+            }
+        }
+        result
+    }
+
+    // Apply the levels that have been set in `other` on top of those in this filter
+    pub(crate) fn merge(&mut self, other: &Filter) {
+        if let Some(level) = other.default {
+            self.set_level(level);
+        }
+        for (module, level) in &other.modules {
+            self.set_module_level(module, *level);
+        }
+    }
+
+    // The level of the most specific module that matches `target`, if any
+    pub(crate) fn module_level(&self, target: &str) -> Option<LevelFilter> {
+        self.modules.iter()
+            .find(|(name, _)| {
+                target.strip_prefix(name.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .map(|(_, level)| *level)
+    }
+
+    // The level that applies to `target`
+    #[cfg(test)]
+    pub(crate) fn level_for(&self, target: &str) -> LevelFilter {
+        self.module_level(target).unwrap_or(self.level())
+    }
+
+    // The most verbose level of any module, or of `default`, for use with `log::set_max_level`
+    pub(crate) fn max_level(&self, default: LevelFilter) -> LevelFilter {
+        self.modules.iter()
+            .map(|(_, level)| *level)
+            .fold(default, |max, level| max.max(level))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use log::LevelFilter;
+
+    use super::{parse_verbosity, Filter};
+
+    // A filter with the valid directives in `spec`
+    fn filter(spec: &str) -> Filter {
+        let mut filter = Filter::default();
+        let _ = filter.add_directives(spec);
+        filter
+    }
+
+    #[test]
+    fn module_directives() {
+        let filter = filter("warn,mycrate=debug,mycrate::net=trace");
+        assert_eq!(filter.level(), LevelFilter::Warn);
+        assert_eq!(filter.level_for("hyper"), LevelFilter::Warn);
+        assert_eq!(filter.level_for("mycrate"), LevelFilter::Debug);
+        assert_eq!(filter.level_for("mycrate::db"), LevelFilter::Debug);
+        assert_eq!(filter.level_for("mycrate::net"), LevelFilter::Trace);
+        assert_eq!(filter.level_for("mycrate::net::tcp"), LevelFilter::Trace);
+    }
+
+    #[test]
+    fn module_name_must_match_whole_path_segments() {
+        let filter = filter("mycrate=debug");
+        assert_eq!(filter.level_for("mycrate_utils"), LevelFilter::Error);
+    }
+
+    #[test]
+    fn most_specific_wins_regardless_of_order() {
+        let filter = filter("mycrate::net=trace,mycrate=info");
+        assert_eq!(filter.level_for("mycrate::net"), LevelFilter::Trace);
+        assert_eq!(filter.level_for("mycrate"), LevelFilter::Info);
+    }
+
+    #[test]
+    fn module_only_keeps_default_level() {
+        let filter = filter("mycrate=debug");
+        assert_eq!(filter.level(), crate::DEFAULT_LOG_LEVEL);
+    }
+
+    #[test]
+    fn invalid_directive_reported() {
+        let mut filter = Filter::default();
+        let error = filter.add_directives("info,mycrate=garbage,other=warn").unwrap_err();
+        assert_eq!(error.to_string(), "invalid verbosity directive 'mycrate=garbage', valid values \
+            are: off, error, warn, info, debug, trace, or 0-5, or a list of them as module=verbosity");
+        assert!(Filter::default().add_directives("=debug").is_err());
+        assert!(Filter::default().add_directives("warn, mycrate=debug").is_ok());
+    }
+
+    #[test]
+    fn parse_verbosity_off_and_numeric() {
+        assert_eq!(parse_verbosity("off"), Ok(LevelFilter::Off));
+        assert_eq!(parse_verbosity("0"), Ok(LevelFilter::Off));
+        assert_eq!(parse_verbosity("1"), Ok(LevelFilter::Error));
+        assert_eq!(parse_verbosity("5"), Ok(LevelFilter::Trace));
+        assert!(parse_verbosity("6").is_err());
+        assert!(parse_verbosity("-1").is_err());
+    }
+
+    #[test]
+    fn module_off() {
+        let filter = filter("info,hyper=off");
+        assert_eq!(filter.level_for("hyper::client"), LevelFilter::Off);
+        assert_eq!(filter.level_for("mycrate"), LevelFilter::Info);
+    }
+
+    #[test]
+    fn parse_verbosity_any_case() {
+        assert_eq!(parse_verbosity("TRACE"), Ok(LevelFilter::Trace));
+        assert_eq!(parse_verbosity("warn"), Ok(LevelFilter::Warn));
+    }
+
+    #[test]
+    fn invalid_directives_ignored() {
+        let filter = filter("info,mycrate=garbage,=debug,other=warn");
+        assert_eq!(filter.level(), LevelFilter::Info);
+        assert_eq!(filter.level_for("mycrate"), LevelFilter::Info);
+        assert_eq!(filter.level_for("other"), LevelFilter::Warn);
+    }
+
+    #[test]
+    fn merge_overrides_levels_set() {
+        let mut merged = filter("info,mycrate=debug,other=warn");
+        merged.merge(&filter("mycrate=trace"));
+        assert_eq!(merged.level(), LevelFilter::Info);
+        assert_eq!(merged.level_for("mycrate"), LevelFilter::Trace);
+        assert_eq!(merged.level_for("other"), LevelFilter::Warn);
+    }
+
+    #[test]
+    fn max_level_is_most_verbose() {
+        let warn = filter("warn,mycrate=debug");
+        assert_eq!(warn.max_level(warn.level()), LevelFilter::Debug);
+        assert_eq!(warn.max_level(LevelFilter::Trace), LevelFilter::Trace);
+        assert_eq!(filter("info").max_level(LevelFilter::Info), LevelFilter::Info);
+    }
+}
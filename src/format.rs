@@ -0,0 +1,422 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use log::Record;
+use termcolor::{Color, ColorSpec, WriteColor};
+
+use crate::ColorScope;
+
+// The name of the current thread, or its id if it doesn't have one
+pub(crate) fn thread_name() -> String {
+    let thread = std::thread::current();
+    match thread.name() {
+        Some(name) => name.to_string(),
+        None => format!("{:?}", thread.id()),
+    }
+}
+
+// The structured key-values of `record`, in the order they were given
+#[cfg(feature = "kv")]
+pub(crate) fn key_values<'a>(record: &'a Record) -> Vec<(log::kv::Key<'a>, log::kv::Value<'a>)> {
+    use log::kv::{Error, Key, Value, VisitSource};
+
+    struct Collect<'kvs>(Vec<(Key<'kvs>, Value<'kvs>)>);
+
+    impl<'kvs> VisitSource<'kvs> for Collect<'kvs> {
+        fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), Error> {
+            self.0.push((key, value));
+            Ok(())
+        }
+    }
+
+    let mut collect = Collect(Vec::new());
+    let _ = record.key_values().visit(&mut collect);
+    collect.0
+}
+
+// Shorten the path of a source file to be relative to the root of the crate it is in, e.g.
+// "/home/me/.cargo/registry/src/index/foo-1.0.0/src/lib.rs" to "src/lib.rs"
+pub(crate) fn short_file(file: &str) -> &str {
+    // The start of each directory in the path, with its name
+    let dirs: Vec<(usize, &str)> = file.match_indices(['/', '\\'])
+        .scan(0, |start, (end, _)| {
+            let dir = (*start, &file[*start..end]);
+            *start = end + 1;
+            Some(dir)
+        })
+        .collect();
+    let root = |names: &[&str]| dirs.iter().rev().find(|(_, dir)| names.contains(dir));
+    match root(&["src"]).or_else(|| root(&["examples", "tests", "benches"])) {
+        Some((start, _)) => &file[*start..],
+        None => file,
+    }
+}
+
+// The colour used for the thread called `name`, which is the same every time for a thread
+fn thread_color(name: &str) -> ColorSpec {
+    const COLORS: [Color; 6] = [Color::Cyan, Color::Green, Color::Yellow, Color::Blue,
+        Color::Magenta, Color::Red];
+    let mut hasher = DefaultHasher::new();
+    name.hash(&mut hasher);
+    let mut spec = ColorSpec::new();
+    spec.set_fg(Some(COLORS[(hasher.finish() % COLORS.len() as u64) as usize]));
+    spec
+}
+
+// Pad `value` to the widest value seen so far in the column with `width`
+fn pad(value: &str, width: &AtomicUsize) -> String {
+    let len = value.chars().count();
+    let width = width.fetch_max(len, Ordering::Relaxed).max(len);
+    format!("{:<width$}", value, width = width)
+}
+
+// The parts of the source of a record that are shown in each log line, with the width of the
+// widest value seen so far in each column so that they line up
+#[derive(Debug, Default)]
+pub(crate) struct Source {
+    thread: bool,
+    target: bool,
+    module_path: bool,
+    location: bool,
+    thread_width: AtomicUsize,
+    widths: [AtomicUsize; 3],
+}
+
+impl Source {
+    pub(crate) fn new(thread: bool, target: bool, module_path: bool, location: bool) -> Self {
+        Source { thread, target, module_path, location, ..Default::default() }
+    }
+
+    fn thread(&self) -> Option<String> {
+        self.thread.then(|| pad(&format!("[{}]", thread_name()), &self.thread_width))
+    }
+
+    fn format(&self, record: &Record) -> Option<String> {
+        let columns = [
+            self.target.then(|| record.target().to_string()),
+            self.module_path.then(|| record.module_path().unwrap_or_default().to_string()),
+            self.location.then(|| match (record.file(), record.line()) {
+                (Some(file), Some(line)) => format!("{}:{}", short_file(file), line),
+                (Some(file), None) => short_file(file).to_string(),
+                (None, _) => String::new(),
+            }),
+        ];
+
+        let mut source: Option<String> = None;
+        for (column, width) in columns.iter().zip(&self.widths) {
+            if let Some(column) = column {
+                let source = match &mut source {
+                    Some(source) => {
+                        source.push(' ');
+                        source
+                    }
+                    None => source.insert(String::new()),
+                };
+                source.push_str(&pad(column, width));
+            }
+        }
+        source
+    }
+}
+
+/// The information about how a log line should be formatted, other than the `Record` itself,
+/// that is passed to a `Formatter`
+#[derive(Debug)]
+pub struct Context<'a> {
+    timestamp: Option<&'a str>,
+    prefix: bool,
+    color: Option<ColorSpec>,
+    source: Option<&'a Source>,
+    color_scope: ColorScope,
+}
+
+impl<'a> Context<'a> {
+    pub(crate) fn new(timestamp: Option<&'a str>, prefix: bool, color: Option<ColorSpec>) -> Self {
+        Context { timestamp, prefix, color, source: None, color_scope: ColorScope::Line }
+    }
+
+    pub(crate) fn with_color_scope(mut self, color_scope: ColorScope) -> Self {
+        self.color_scope = color_scope;
+        self
+    }
+
+    pub(crate) fn with_source(mut self, source: &'a Source) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// The timestamp for the log line, in the format set on the logger, or `None` if timestamps
+    /// are not enabled
+    pub fn timestamp(&self) -> Option<&str> {
+        self.timestamp
+    }
+
+    /// Whether the log line should be prefixed with the level of the record
+    pub fn prefix(&self) -> bool {
+        self.prefix
+    }
+
+    /// The name of the current thread (or its id if it has no name) in brackets, padded to line
+    /// up with previous log lines, or `None` if the logger is not configured to show it
+    pub fn thread(&self) -> Option<String> {
+        self.source.and_then(Source::thread)
+    }
+
+    /// The colour for the current thread, which is the same for every log line written by the
+    /// thread, or `None` if the output is not coloured
+    pub fn thread_color(&self) -> Option<ColorSpec> {
+        self.color.as_ref().map(|_| thread_color(&thread_name()))
+    }
+
+    /// The target, module path and file:line (relative to the crate root) of `record` that the
+    /// logger is configured to show, separated by spaces and padded to line up with previous log
+    /// lines, or `None` if none of them are shown
+    pub fn source(&self, record: &Record) -> Option<String> {
+        self.source.and_then(|source| source.format(record))
+    }
+
+    /// The colour for the level of the record, or `None` if the output is not coloured
+    pub fn color(&self) -> Option<&ColorSpec> {
+        self.color.as_ref()
+    }
+
+    /// Which part of the log line should be coloured with `color()`. When it is
+    /// `ColorScope::Line` the logger colours the whole line, unless `Formatter::colors()` is true.
+    pub fn color_scope(&self) -> ColorScope {
+        self.color_scope
+    }
+
+    /// Start colouring `out` with the colour for the level of the record, if the output is
+    /// coloured
+    pub fn set_color(&self, out: &mut dyn WriteColor) -> io::Result<()> {
+        match &self.color {
+            Some(color) => out.set_color(color),
+            None => Ok(()),
+        }
+    }
+
+    /// Stop colouring `out`, if the output is coloured
+    pub fn reset(&self, out: &mut dyn WriteColor) -> io::Result<()> {
+        match &self.color {
+            Some(_) => out.reset(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Implement `Formatter` to control the layout of each log line written by a `SimpleLogger`,
+/// and install it using `SimpleLoggerBuilder::formatter()`
+///
+/// # Example
+/// ```
+/// use std::io;
+/// use log::{info, Record};
+/// use simplog::{Context, Formatter, SimpleLogger};
+/// use termcolor::WriteColor;
+///
+/// struct PidFormatter;
+///
+/// impl Formatter for PidFormatter {
+///     fn format(&self, out: &mut dyn WriteColor, record: &Record, _context: &Context)
+///         -> io::Result<()> {
+///         write!(out, "[{}] {} {}", std::process::id(), record.level(), record.args())
+///     }
+/// }
+///
+/// SimpleLogger::builder().formatter(PidFormatter).init();
+/// info!("Hello World!");
+/// // Produces "[1234] INFO Hello World!"
+/// ```
+pub trait Formatter: Send + Sync {
+    /// Write the log line for `record` to `out`, without a trailing newline which is added by the
+    /// logger. Colour it only using `Context::set_color()` and `Context::reset()`, or the
+    /// `WriteColor` methods of `out`, so that it is coloured correctly on every kind of console.
+    fn format(&self, out: &mut dyn WriteColor, record: &Record, context: &Context)
+        -> io::Result<()>;
+
+    /// Return true if this formatter colours the log line itself, using `Context::set_color()`
+    /// and `Context::reset()`, so the logger should not colour the whole line. The default is
+    /// false.
+    fn colors(&self) -> bool {
+        false
+    }
+
+    /// Return false if the log lines of this formatter must never be coloured, such as those of
+    /// a machine readable format. The logger then never colours them, and `Context::color()` is
+    /// `None`. The default is true.
+    fn uses_color(&self) -> bool {
+        true
+    }
+}
+
+/// The `Formatter` used by default. It writes the timestamp (if enabled), the thread (if enabled,
+/// see `Context::thread()`) in its own colour, the level (if the prefix is enabled, coloured by
+/// itself when the colour scope is `ColorScope::Level`), the source of the record (if enabled,
+/// see `Context::source()`) and then the message, e.g. "1.246717ms INFO\t- Hello World". With
+/// the "kv" feature, any key-values of the record follow the message as "key=value".
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultFormatter;
+
+impl Formatter for DefaultFormatter {
+    fn format(&self, out: &mut dyn WriteColor, record: &Record, context: &Context)
+        -> io::Result<()> {
+        if let Some(timestamp) = context.timestamp() {
+            write!(out, "{} ", timestamp)?;
+        }
+        if let Some(thread) = context.thread() {
+            match context.thread_color() {
+                Some(color) => {
+                    out.set_color(&color)?;
+                    write!(out, "{}", thread)?;
+                    context.reset(out)?;
+                    if context.color_scope() == ColorScope::Line {
+                        context.set_color(out)?;
+                    }
+                    write!(out, " ")?;
+                }
+                None => write!(out, "{} ", thread)?,
+            }
+        }
+        if context.prefix() {
+            if context.color_scope() == ColorScope::Level {
+                context.set_color(out)?;
+                write!(out, "{}", record.level())?;
+                context.reset(out)?;
+                write!(out, "\t- ")?;
+            } else {
+                write!(out, "{}\t- ", record.level())?;
+            }
+        }
+        if let Some(source) = context.source(record) {
+            write!(out, "{} ", source)?;
+        }
+        write!(out, "{}", record.args())?;
+        #[cfg(feature = "kv")]
+        for (key, value) in key_values(record) {
+            write!(out, " {}={}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use log::{Level, Record};
+
+    use super::{short_file, Context, DefaultFormatter, Formatter, Source};
+
+    fn format(timestamp: Option<&str>, prefix: bool) -> String {
+        let mut out = termcolor::Buffer::ansi();
+        let record = Record::builder()
+            .level(Level::Info)
+            .args(format_args!("Hello World"))
+            .build();
+        DefaultFormatter.format(&mut out, &record, &Context::new(timestamp, prefix, None)).unwrap();
+        String::from_utf8(out.into_inner()).unwrap()
+    }
+
+    #[cfg(feature = "kv")]
+    #[test]
+    fn default_format_key_values() {
+        let key_values = [("user", log::kv::Value::from(42)), ("ok", log::kv::Value::from(true))];
+        let record = Record::builder()
+            .level(Level::Info)
+            .args(format_args!("login"))
+            .key_values(&key_values)
+            .build();
+        let mut out = termcolor::Buffer::ansi();
+        DefaultFormatter.format(&mut out, &record, &Context::new(None, false, None)).unwrap();
+        assert_eq!(String::from_utf8(out.into_inner()).unwrap(), "login user=42 ok=true");
+    }
+
+    #[test]
+    fn short_files() {
+        assert_eq!(short_file("src/lib.rs"), "src/lib.rs");
+        assert_eq!(short_file("crates/app/src/net/mod.rs"), "src/net/mod.rs");
+        assert_eq!(short_file("/home/me/.cargo/registry/src/index/foo-1.0.0/src/lib.rs"), "src/lib.rs");
+        assert_eq!(short_file("C:\\work\\app\\src\\main.rs"), "src\\main.rs");
+        assert_eq!(short_file("app/examples/demo.rs"), "examples/demo.rs");
+        assert_eq!(short_file("main.rs"), "main.rs");
+    }
+
+    #[test]
+    fn source_columns() {
+        let source = Source::new(false, true, false, true);
+        let line = |target: &str, file: &str, line: u32| {
+            let record = Record::builder()
+                .level(Level::Info)
+                .target(target)
+                .file(Some(file))
+                .line(Some(line))
+                .args(format_args!("Hello World"))
+                .build();
+            let mut out = termcolor::Buffer::ansi();
+            let context = Context::new(None, true, None).with_source(&source);
+            DefaultFormatter.format(&mut out, &record, &context).unwrap();
+            String::from_utf8(out.into_inner()).unwrap()
+        };
+
+        assert_eq!(line("app::net", "app/src/net.rs", 7), "INFO\t- app::net src/net.rs:7 Hello World");
+        assert_eq!(line("app", "app/src/main.rs", 12), "INFO\t- app      src/main.rs:12 Hello World");
+        assert_eq!(line("app::net", "app/src/net.rs", 7), "INFO\t- app::net src/net.rs:7   Hello World");
+        assert_eq!(Context::new(None, true, None).with_source(&Source::default())
+                       .source(&Record::builder().build()), None);
+    }
+
+    #[test]
+    fn thread_column() {
+        let source = Source::new(true, false, false, false);
+        let line = |color: Option<termcolor::ColorSpec>| {
+            let record = Record::builder()
+                .level(Level::Info)
+                .args(format_args!("Hello World"))
+                .build();
+            let mut out = termcolor::Buffer::ansi();
+            let context = Context::new(None, true, color).with_source(&source);
+            DefaultFormatter.format(&mut out, &record, &context).unwrap();
+            String::from_utf8(out.into_inner()).unwrap()
+        };
+
+        std::thread::scope(|scope| {
+            std::thread::Builder::new().name("worker-10".into())
+                .spawn_scoped(scope, || line(None)).unwrap().join().unwrap();
+            let short = std::thread::Builder::new().name("io".into())
+                .spawn_scoped(scope, || line(None)).unwrap().join().unwrap();
+            assert_eq!(short, "[io]        INFO\t- Hello World");
+
+            let first = std::thread::Builder::new().name("io".into())
+                .spawn_scoped(scope, || line(Some(termcolor::ColorSpec::new())))
+                .unwrap().join().unwrap();
+            let second = std::thread::Builder::new().name("io".into())
+                .spawn_scoped(scope, || line(Some(termcolor::ColorSpec::new())))
+                .unwrap().join().unwrap();
+            assert!(first.starts_with("\x1b["));
+            assert_eq!(first, second);
+        });
+    }
+
+    #[test]
+    fn level_color_scope() {
+        let record = Record::builder()
+            .level(Level::Warn)
+            .args(format_args!("Hello World"))
+            .build();
+        let mut color = termcolor::ColorSpec::new();
+        color.set_fg(Some(termcolor::Color::Yellow));
+        let context = Context::new(None, true, Some(color))
+            .with_color_scope(crate::ColorScope::Level);
+        let mut out = termcolor::Buffer::ansi();
+        DefaultFormatter.format(&mut out, &record, &context).unwrap();
+        assert_eq!(String::from_utf8(out.into_inner()).unwrap(), "\x1b[0m\x1b[33mWARN\x1b[0m\t- Hello World");
+    }
+
+    #[test]
+    fn default_format() {
+        assert_eq!(format(None, false), "Hello World");
+        assert_eq!(format(None, true), "INFO\t- Hello World");
+        assert_eq!(format(Some("1.2ms"), true), "1.2ms INFO\t- Hello World");
+        assert_eq!(format(Some("1.2ms"), false), "1.2ms Hello World");
+    }
+}
@@ -0,0 +1,144 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use log::LevelFilter;
+
+use crate::filter::Filter;
+
+/*
+    The settings of a `SimpleLogger` that can be changed while it is in use, shared between the
+    logger and any `LoggerHandle`s to it. Atomics are used so that logging does not need a lock.
+*/
+#[derive(Debug)]
+pub(crate) struct Settings {
+    level: AtomicUsize,
+    prefix: AtomicBool,
+    timestamp: AtomicBool,
+    installed: AtomicBool,
+    // the most verbose level set on any sink, which doesn't change
+    sink_level: LevelFilter,
+}
+
+impl Settings {
+    pub(crate) fn new(level: LevelFilter, prefix: bool, timestamp: bool, sink_level: LevelFilter)
+        -> Self {
+        Settings {
+            level: AtomicUsize::new(level as usize),
+            prefix: AtomicBool::new(prefix),
+            timestamp: AtomicBool::new(timestamp),
+            installed: AtomicBool::new(false),
+            sink_level,
+        }
+    }
+
+    pub(crate) fn level(&self) -> LevelFilter {
+        let level = self.level.load(Ordering::Relaxed);
+        LevelFilter::iter().nth(level).unwrap_or(LevelFilter::Trace)
+    }
+
+    pub(crate) fn prefix(&self) -> bool {
+        self.prefix.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn timestamp(&self) -> bool {
+        self.timestamp.load(Ordering::Relaxed)
+    }
+
+    // The most verbose level of any module or sink, for use with `log::set_max_level`
+    pub(crate) fn max_level(&self, filter: &Filter) -> LevelFilter {
+        filter.max_level(self.level().max(self.sink_level))
+    }
+
+    pub(crate) fn set_installed(&self) {
+        self.installed.store(true, Ordering::Relaxed);
+    }
+}
+
+/// A handle to a `SimpleLogger` that can be used to change its settings at runtime, without
+/// needing to reinstall it. It is returned by the `init` functions, or by `SimpleLogger::handle()`,
+/// and can be cloned and sent to other threads.
+///
+/// # Example
+/// ```
+/// use log::{debug, LevelFilter};
+/// use simplog::SimpleLogger;
+///
+/// let handle = SimpleLogger::init(Some("info"));
+/// debug!("Not output");
+/// handle.set_level(LevelFilter::Debug);
+/// debug!("Hello World!");
+/// // Produces "DEBUG  - Hello World!"
+/// ```
+#[derive(Clone, Debug)]
+pub struct LoggerHandle {
+    settings: Arc<Settings>,
+    filter: Arc<Filter>,
+}
+
+impl LoggerHandle {
+    pub(crate) fn new(settings: Arc<Settings>, filter: Arc<Filter>) -> Self {
+        LoggerHandle { settings, filter }
+    }
+
+    /// The log level used for all modules that don't have their own level set
+    pub fn level(&self) -> LevelFilter {
+        self.settings.level()
+    }
+
+    /// Set the log level used for all modules that don't have their own level set.
+    /// If the logger is installed, the `log` framework's maximum log level is also updated.
+    pub fn set_level(&self, level: LevelFilter) {
+        self.settings.level.store(level as usize, Ordering::Relaxed);
+        if self.settings.installed.load(Ordering::Relaxed) {
+            log::set_max_level(self.settings.max_level(&self.filter));
+        }
+    }
+
+    /// Set whether each log line is prefixed with the level that produced it
+    pub fn set_prefix(&self, prefix: bool) {
+        self.settings.prefix.store(prefix, Ordering::Relaxed);
+    }
+
+    /// Set whether each log line is prefixed with a timestamp in the configured format
+    pub fn set_timestamp(&self, timestamp: bool) {
+        self.settings.timestamp.store(timestamp, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use log::{Level, LevelFilter, Log, Metadata};
+
+    use crate::SimpleLogger;
+
+    #[test]
+    fn set_level() {
+        let logger = SimpleLogger::builder().level(LevelFilter::Info).build();
+        let handle = logger.handle();
+        let debug = Metadata::builder().level(Level::Debug).build();
+        assert!(!logger.enabled(&debug));
+        handle.set_level(LevelFilter::Debug);
+        assert_eq!(handle.level(), LevelFilter::Debug);
+        assert!(logger.enabled(&debug));
+        handle.set_level(LevelFilter::Off);
+        assert!(!logger.enabled(&Metadata::builder().level(Level::Error).build()));
+    }
+
+    #[test]
+    fn set_level_keeps_module_levels() {
+        let logger = SimpleLogger::builder().directives("info,hyper=off").build();
+        logger.handle().set_level(LevelFilter::Trace);
+        assert!(!logger.enabled(&Metadata::builder().level(Level::Error).target("hyper").build()));
+        assert!(logger.enabled(&Metadata::builder().level(Level::Trace).target("mycrate").build()));
+    }
+
+    #[test]
+    fn set_prefix_and_timestamp() {
+        let logger = SimpleLogger::builder().prefix(true).timestamp(false).build();
+        let handle = logger.handle();
+        handle.set_prefix(false);
+        handle.set_timestamp(true);
+        assert!(!logger.settings.prefix());
+        assert!(logger.settings.timestamp());
+    }
+}
@@ -0,0 +1,183 @@
+use std::io::{self, Write};
+
+use log::Record;
+use termcolor::WriteColor;
+
+use crate::format::thread_name;
+use crate::{Context, Formatter};
+
+// Write `value` to `out` as a JSON string, with quotes and escaping
+fn write_string<W: Write + ?Sized>(out: &mut W, value: &str) -> io::Result<()> {
+    out.write_all(b"\"")?;
+    let mut start = 0;
+    for (index, c) in value.char_indices() {
+        let escape = match c {
+            '"' => Some("\\\""),
+            '\\' => Some("\\\\"),
+            '\n' => Some("\\n"),
+            '\r' => Some("\\r"),
+            '\t' => Some("\\t"),
+            c if c < ' ' => None,
+            _ => continue,
+        };
+        out.write_all(&value.as_bytes()[start..index])?;
+        match escape {
+            Some(escape) => out.write_all(escape.as_bytes())?,
+            None => write!(out, "\\u{:04x}", c as u32)?,
+        }
+        start = index + c.len_utf8();
+    }
+    out.write_all(&value.as_bytes()[start..])?;
+    out.write_all(b"\"")
+}
+
+// Write the key-value `value` to `out` as a JSON boolean, number or string
+#[cfg(feature = "kv")]
+fn write_value<W: Write + ?Sized>(out: &mut W, value: &log::kv::Value) -> io::Result<()> {
+    if let Some(value) = value.to_bool() {
+        write!(out, "{}", value)
+    } else if let Some(value) = value.to_i64() {
+        write!(out, "{}", value)
+    } else if let Some(value) = value.to_u64() {
+        write!(out, "{}", value)
+    } else if let Some(value) = value.to_f64().filter(|value| value.is_finite()) {
+        write!(out, "{}", value)
+    } else {
+        write_string(out, &value.to_string())
+    }
+}
+
+/// A `Formatter` that writes each log line as a JSON object (JSON Lines), for ingestion by log
+/// processing systems. The object has the fields "timestamp" (if timestamps are enabled),
+/// "level", "target", "module_path", "file" and "line" (if known), "thread" and "message".
+/// With the "kv" feature, any key-values of the record are added as fields after them, as
+/// JSON numbers and booleans where possible. Output is never coloured.
+///
+/// # Example
+/// ```
+/// use log::info;
+/// use simplog::SimpleLogger;
+///
+/// SimpleLogger::builder().json().init();
+/// info!("Hello World!");
+/// // Produces {"timestamp":"2024-03-01T12:34:56.789Z","level":"INFO","target":"app",...,"message":"Hello World!"}
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct JsonFormatter;
+
+impl Formatter for JsonFormatter {
+    fn format(&self, out: &mut dyn WriteColor, record: &Record, context: &Context)
+        -> io::Result<()> {
+        out.write_all(b"{")?;
+        if let Some(timestamp) = context.timestamp() {
+            out.write_all(b"\"timestamp\":")?;
+            write_string(out, timestamp)?;
+            out.write_all(b",")?;
+        }
+        out.write_all(b"\"level\":")?;
+        write_string(out, record.level().as_str())?;
+        out.write_all(b",\"target\":")?;
+        write_string(out, record.target())?;
+        if let Some(module_path) = record.module_path() {
+            out.write_all(b",\"module_path\":")?;
+            write_string(out, module_path)?;
+        }
+        if let Some(file) = record.file() {
+            out.write_all(b",\"file\":")?;
+            write_string(out, file)?;
+        }
+        if let Some(line) = record.line() {
+            write!(out, ",\"line\":{}", line)?;
+        }
+        out.write_all(b",\"thread\":")?;
+        write_string(out, &thread_name())?;
+        out.write_all(b",\"message\":")?;
+        write_string(out, &record.args().to_string())?;
+        #[cfg(feature = "kv")]
+        for (key, value) in crate::format::key_values(record) {
+            out.write_all(b",")?;
+            write_string(out, key.as_str())?;
+            out.write_all(b":")?;
+            write_value(out, &value)?;
+        }
+        out.write_all(b"}")
+    }
+
+    fn uses_color(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use log::{Level, Record};
+
+    use super::{write_string, JsonFormatter};
+    use crate::{Context, Formatter};
+
+    fn escape(value: &str) -> String {
+        let mut out = Vec::new();
+        write_string(&mut out, value).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(escape("plain"), "\"plain\"");
+        assert_eq!(escape("say \"hi\"\\n"), "\"say \\\"hi\\\"\\\\n\"");
+        assert_eq!(escape("line 1\nline 2\ttab"), "\"line 1\\nline 2\\ttab\"");
+        assert_eq!(escape("bell\u{7} ünïcode"), "\"bell\\u0007 ünïcode\"");
+    }
+
+    // Format a record from a thread named "worker", so the thread name is known
+    fn format(timestamp: Option<&str>, location: bool) -> String {
+        std::thread::scope(|scope| {
+            std::thread::Builder::new().name("worker".into()).spawn_scoped(scope, || {
+                let mut builder = Record::builder();
+                builder.level(Level::Info).target("app");
+                if location {
+                    builder.module_path(Some("app::net")).file(Some("src/net.rs")).line(Some(7));
+                }
+                let mut out = termcolor::Buffer::no_color();
+                let context = Context::new(timestamp, true, None);
+                JsonFormatter.format(&mut out, &builder.args(format_args!("multi\nline")).build(),
+                                     &context).unwrap();
+                String::from_utf8(out.into_inner()).unwrap()
+            }).unwrap().join().unwrap()
+        })
+    }
+
+    #[cfg(feature = "kv")]
+    #[test]
+    fn key_values() {
+        use log::kv::Value;
+
+        let key_values = [("user", Value::from(42)), ("ok", Value::from(true)),
+            ("ratio", Value::from(0.5)), ("name", Value::from("a \"b\""))];
+        let record = Record::builder()
+            .level(Level::Info)
+            .target("app")
+            .args(format_args!("login"))
+            .key_values(&key_values)
+            .build();
+        let mut out = termcolor::Buffer::no_color();
+        JsonFormatter.format(&mut out, &record, &Context::new(None, true, None)).unwrap();
+        assert!(String::from_utf8(out.into_inner()).unwrap().ends_with(
+            "\"message\":\"login\",\"user\":42,\"ok\":true,\"ratio\":0.5,\"name\":\"a \\\"b\\\"\"}"));
+    }
+
+    #[test]
+    fn json_line() {
+        assert_eq!(format(Some("2024-03-01T12:34:56Z"), true),
+                   "{\"timestamp\":\"2024-03-01T12:34:56Z\",\"level\":\"INFO\",\"target\":\"app\",\
+                   \"module_path\":\"app::net\",\"file\":\"src/net.rs\",\"line\":7,\
+                   \"thread\":\"worker\",\"message\":\"multi\\nline\"}");
+    }
+
+    #[test]
+    fn no_timestamp_or_location() {
+        assert_eq!(format(None, false),
+                   "{\"level\":\"INFO\",\"target\":\"app\",\"thread\":\"worker\",\
+                   \"message\":\"multi\\nline\"}");
+    }
+}
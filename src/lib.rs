@@ -1,19 +1,57 @@
 #![deny(missing_docs)]
 
-//! `simplog` is as its name suggests a very simpler logging implementation for rust
-//! It provides three main features
-//!    - Settable log level (or verbosity) (default is Log::Level::Error)
-//!    - Optional prefix each log line with the Level it corresponds to (after timestamp if present)
-//!    - Optional timestamp prefixed to each line
+//! `simplog` is as its name suggests a very simple logging implementation for rust.
+//! A `SimpleLogger` is configured and installed using `SimpleLogger::builder()`, which sets
+//!    - The log level (or verbosity), `Error` by default, for all modules or for each module,
+//!      from the builder or the environment, and changeable at runtime using a `LoggerHandle`
+//!    - The layout of each log line: the optional level prefix, timestamp and source of the
+//!      `DefaultFormatter`, a `PatternFormatter`, JSON, logfmt or any other `Formatter`
+//!    - Colouring of log lines by level, using a `Palette`, when the `ColorMode` allows it
+//!    - Where log lines are written: STDOUT and STDERR, a file that can be rotated, any writer,
+//!      or several `Sink`s each with its own level, formatter and colour mode
+//!
+//! The `init` functions configure and install a logger for the most common cases.
 
-use std::io;
-use std::io::{stdout, Write};
-use std::str::FromStr;
+use std::fmt;
+use std::io::{self, Write};
 
-use log::{Level, Log, Metadata, Record};
-use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
-use atty::Stream;
-use std::time::Instant;
+use log::{LevelFilter, Log, Metadata, Record};
+use termcolor::{Buffer, WriteColor};
+use std::time::SystemTime;
+
+mod builder;
+mod color;
+mod error;
+mod file;
+mod filter;
+mod format;
+mod handle;
+mod json;
+mod logfmt;
+mod pattern;
+mod sink;
+mod timestamp;
+mod writer;
+
+pub use builder::{SimpleLoggerBuilder, Target, RUST_LOG_ENV, SIMPLOG_LEVEL_ENV};
+pub use color::{ColorMode, ColorScope, Palette};
+pub use error::{Error, ParseError};
+pub use file::{Period, Rotation};
+pub use filter::parse_verbosity;
+pub use format::{Context, DefaultFormatter, Formatter};
+pub use handle::LoggerHandle;
+pub use json::JsonFormatter;
+pub use logfmt::LogfmtFormatter;
+pub use pattern::PatternFormatter;
+pub use sink::Sink;
+pub use timestamp::{Precision, TimestampFormat};
+
+use filter::Filter;
+use format::Source;
+use handle::Settings;
+use sink::SinkOutput;
+use timestamp::Clock;
+use std::sync::Arc;
 
 /// Use the `SimpleLogger` struct to initialize a logger. From then on, the rust `log` framework
 /// should be used to output log statements as usual.
@@ -31,19 +69,46 @@ use std::time::Instant;
 /// ```
 #[derive(Clone)]
 pub struct SimpleLogger {
-    log_level: Level,
-    prefix: bool,
-    start: Instant,
-    timestamp: bool,
+    pub(crate) filter: Arc<Filter>,
+    pub(crate) settings: Arc<Settings>,
+    pub(crate) clock: Clock,
+    pub(crate) source: Arc<Source>,
+    pub(crate) palette: Palette,
+    pub(crate) color_scope: ColorScope,
+    pub(crate) sinks: Vec<SinkOutput>,
 }
 
-const DEFAULT_LOG_LEVEL: Level = Level::Error;
+pub(crate) const DEFAULT_LOG_LEVEL: LevelFilter = LevelFilter::Error;
 
 impl SimpleLogger {
+    /// Create a `SimpleLoggerBuilder` that can be used to configure the logger before
+    /// building or installing it
+    ///
+    /// # Example
+    /// ```
+    /// use log::{info, LevelFilter};
+    /// use simplog::SimpleLogger;
+    ///
+    /// SimpleLogger::builder().level(LevelFilter::Info).prefix(false).init();
+    /// info!("Hello World!");
+    /// // Produces "Hello World"
+    /// ```
+    pub fn builder() -> SimpleLoggerBuilder {
+        SimpleLoggerBuilder::new()
+    }
+
     /// Initialize the logger, with an optionally provided log level (`verbosity`) in a `&str`
     /// If `None` is provided -> The log level will be set to `Error`
     /// If 'Some(`verbosity') is a &str with a valid log level, the string will be parsed and if
-    /// valid set as the log level.
+    /// valid set as the log level. Valid log levels are "off", "error", "warn", "info", "debug"
+    /// and "trace" in any case, or the numbers 0 to 5 for the same levels.
+    ///
+    /// `verbosity` may also contain a comma separated list of `module=level` directives that set
+    /// the log level for a module (log target) and those below it, with the most specific module
+    /// taking precedence, e.g. `"warn,mycrate=debug,mycrate::net=trace"`
+    ///
+    /// If `verbosity` is not valid, the valid parts are used and a warning is written to the log
+    /// output. Use `parse_verbosity` or `SimpleLogger::builder().strict(true)` to check it.
     ///
     /// # Example
     /// ```
@@ -54,7 +119,7 @@ impl SimpleLogger {
     /// info!("Hello World!");
     /// // Produces "Hello World"
     /// ```
-    pub fn init(verbosity: Option<&str>) {
+    pub fn init(verbosity: Option<&str>) -> LoggerHandle {
         Self::init_prefix(verbosity, true)
     }
 
@@ -71,8 +136,8 @@ impl SimpleLogger {
     /// info!("Hello World!");
     /// // Produces "INFO   - Hello World"
     /// ```
-    pub fn init_prefix(verbosity: Option<&str>, prefix: bool) {
-        Self::init_prefix_timestamp(verbosity, prefix, false);
+    pub fn init_prefix(verbosity: Option<&str>, prefix: bool) -> LoggerHandle {
+        Self::init_prefix_timestamp(verbosity, prefix, false)
     }
 
     /// Initialize the logger, with an optionally provided log level (`verbosity`) in a &str
@@ -86,118 +151,267 @@ impl SimpleLogger {
     /// use log::info;
     /// use simplog::SimpleLogger;
     ///
-    /// let mut logger = SimpleLogger::init_prefix_timestamp(Some("info"), false, true);
+    /// let handle = SimpleLogger::init_prefix_timestamp(Some("info"), false, true);
     /// info!("Hello World!");
     /// // Produces "1.246717ms   Hello World"
     /// ```
-    pub fn init_prefix_timestamp(verbosity: Option<&str>, prefix: bool, timestamp: bool) {
-        let log_level = parse_log_level(verbosity);
-        let simplogger = SimpleLogger {
-            log_level,
-            prefix,
-            start: Instant::now(),
-            timestamp,
-        };
-        let logger = Box::new(simplogger);
-        let _ = log::set_boxed_logger(logger);
-        log::set_max_level(log_level.to_level_filter());
+    pub fn init_prefix_timestamp(verbosity: Option<&str>, prefix: bool, timestamp: bool)
+        -> LoggerHandle {
+        Self::builder_prefix_timestamp(verbosity, prefix, timestamp).init()
+    }
+
+    /// Same as `init`, but returns an `Error` if a logger has already been installed
+    ///
+    /// # Example
+    /// ```
+    /// use simplog::SimpleLogger;
+    ///
+    /// SimpleLogger::try_init(Some("info")).expect("Could not initialize logger");
+    /// assert!(SimpleLogger::try_init(Some("info")).is_err());
+    /// ```
+    pub fn try_init(verbosity: Option<&str>) -> Result<LoggerHandle, Error> {
+        Self::try_init_prefix(verbosity, true)
+    }
+
+    /// Same as `init_prefix`, but returns an `Error` if a logger has already been installed
+    pub fn try_init_prefix(verbosity: Option<&str>, prefix: bool)
+        -> Result<LoggerHandle, Error> {
+        Self::try_init_prefix_timestamp(verbosity, prefix, false)
+    }
+
+    /// Same as `init_prefix_timestamp`, but returns an `Error` if a logger has already been
+    /// installed. On error the `log` framework's maximum log level is left unchanged.
+    pub fn try_init_prefix_timestamp(verbosity: Option<&str>, prefix: bool, timestamp: bool)
+        -> Result<LoggerHandle, Error> {
+        Self::builder_prefix_timestamp(verbosity, prefix, timestamp).try_init()
+    }
+
+    fn builder_prefix_timestamp(verbosity: Option<&str>, prefix: bool, timestamp: bool)
+        -> SimpleLoggerBuilder {
+        let builder = Self::builder()
+            .prefix(prefix)
+            .timestamp(timestamp);
+        match verbosity {
+            Some(verbosity) => builder.directives(verbosity),
+            None => builder,
+        }
+    }
+
+    /// Initialize the logger, with the log level read from the `RUST_LOG` environment variable.
+    /// If it is not set the log level will be `Error`. The value can be a log level or a list of
+    /// directives, as described for `init`. To use a different variable, such as
+    /// `SIMPLOG_LEVEL_ENV`, use `SimpleLogger::builder().env(SIMPLOG_LEVEL_ENV).init()`
+    ///
+    /// # Example
+    /// ```
+    /// use log::info;
+    /// use simplog::SimpleLogger;
+    ///
+    /// // With RUST_LOG=info set in the environment
+    /// SimpleLogger::init_from_env();
+    /// info!("Hello World!");
+    /// // Produces "INFO   - Hello World"
+    /// ```
+    pub fn init_from_env() -> LoggerHandle {
+        Self::builder().env(RUST_LOG_ENV).init()
+    }
+
+    /// Same as `init_from_env`, but returns an `Error` if a logger has already been installed
+    pub fn try_init_from_env() -> Result<LoggerHandle, Error> {
+        Self::builder().env(RUST_LOG_ENV).try_init()
+    }
+
+    /// Get a `LoggerHandle` that can be used to change the settings of this logger at runtime,
+    /// including after it has been installed
+    pub fn handle(&self) -> LoggerHandle {
+        LoggerHandle::new(self.settings.clone(), self.filter.clone())
     }
 }
 
-/*
-    Parse an optional String argument ("debug", "info", "trace", "error") into a log level that
-    can be used to set verbosity of output. If none is supplied or there is an error parsing the
-    String, then the DEFAULT_LOG_LEVEL of "Error" is used.
-*/
-fn parse_log_level(arg: Option<&str>) -> Level {
-    match arg {
-        None => DEFAULT_LOG_LEVEL,
-        Some(arg) => match Level::from_str(arg) {
-            Ok(ll) => ll,
-            Err(_) => DEFAULT_LOG_LEVEL
+impl SimpleLogger {
+    // Write a warning about the logger itself to STDERR, so that it is not mixed up with log
+    // output that is piped to another program, unless logging is turned off
+    pub(crate) fn warn(&self, args: fmt::Arguments) {
+        if let Some(warning) = self.warning(args) {
+            let _ = writeln!(io::stderr().lock(), "{}", warning);
+        }
+    }
+
+    fn warning(&self, args: fmt::Arguments) -> Option<String> {
+        (self.settings.max_level(&self.filter) != LevelFilter::Off)
+            .then(|| format!("{}: WARN - {}", module_path!(), args))
+    }
+
+    // The log level for `target` of an output with its own `level`, which caps the level set
+    // for the module, or that uses the default level (replaced by the module level) if `None`
+    fn level_for(&self, target: &str, level: Option<LevelFilter>) -> LevelFilter {
+        let module_level = self.filter.module_level(target);
+        match level {
+            Some(level) => module_level.map_or(level, |module_level| module_level.min(level)),
+            None => module_level.unwrap_or_else(|| self.settings.level()),
+        }
+    }
+
+    // Write `record` to each sink whose log level allows it, with the sink's own timestamp if it
+    // has one. The whole line is written at once, so lines from different threads don't get
+    // mixed up.
+    fn write(&self, record: &Record) {
+        let now = SystemTime::now();
+        let timestamp = |clock: &Clock| {
+            let mut timestamp = String::new();
+            clock.write(&mut timestamp, now);
+            timestamp
+        };
+        let default_timestamp = self.settings.timestamp().then(|| timestamp(&self.clock));
+
+        let level = record.level();
+        for sink in self.sinks.iter()
+            .filter(|sink| level <= self.level_for(record.target(), sink.level)) {
+            let sink_timestamp = sink.clock.as_ref().map(timestamp);
+            let timestamp = sink_timestamp.as_deref().or(default_timestamp.as_deref());
+            let mut line = sink.buffer(level);
+            if self.format(&mut line, sink.formatter.as_ref(), record, timestamp).is_ok() {
+                sink.write_line(level, &line);
+            }
+        }
+    }
+
+    // Format `record` into `line`, a buffer from `SinkOutput::buffer()`, with a trailing newline.
+    // If the buffer is coloured the line is coloured by its level (as a whole, or by the
+    // formatter), and if any colour was written it is reset before the newline so it cannot leak
+    // into whatever is written next.
+    fn format(&self, line: &mut Buffer, formatter: &dyn Formatter, record: &Record,
+              timestamp: Option<&str>) -> io::Result<()> {
+        let color = (line.supports_color() && formatter.uses_color())
+            .then(|| self.palette.get(record.level()).clone());
+        let color_line = self.color_scope == ColorScope::Line && !formatter.colors();
+        if let (Some(color), true) = (&color, color_line) {
+            line.set_color(color)?;
+        }
+        let reset = color.is_some() && (color_line || formatter.colors());
+
+        let context = Context::new(timestamp, self.settings.prefix(), color)
+            .with_source(&self.source)
+            .with_color_scope(self.color_scope);
+        formatter.format(line, record, &context)?;
+
+        if reset {
+            line.reset()?;
         }
+        line.write_all(b"\n")
     }
 }
 
 /*
     Implement the simpler logger.
     - depending on the way Logger was created a prefix with the level of the output is printed or not
-    - "Error" level output is printed to STDERR, all other levels are printed to STDOUT
+    - by default "Error" level output is printed to STDERR, all other levels are printed to STDOUT
+    - output can be written to a file instead of, or as well as, the console
+    - the console can be replaced by any writer, such as a socket or a buffer
+    - or all of these can be replaced by a list of sinks, each with its own level and format
 */
 impl Log for SimpleLogger {
+    // Enabled if any sink would write it
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= self.log_level
+        self.sinks.iter()
+            .any(|sink| metadata.level() <= self.level_for(metadata.target(), sink.level))
     }
 
     fn log(&self, record: &Record) {
         if self.enabled(record.metadata()) {
-            let mut stdout = StandardStream::stdout(ColorChoice::Always);
-
-            let message = if self.prefix {
-                format!("{}\t- {}", record.level(), record.args())
-            } else {
-                format!("{}", record.args())
-            };
-
-            if atty::is(Stream::Stdout) {
-                match record.level() {
-                    Level::Error => stdout.set_color(ColorSpec::new().set_fg(Some(Color::Red))).unwrap(),
-                    Level::Warn => stdout.set_color(ColorSpec::new().set_fg(Some(Color::Yellow))).unwrap(),
-                    Level::Info=> stdout.set_color(ColorSpec::new().set_fg(Some(Color::Magenta))).unwrap(),
-                    Level::Debug=> stdout.set_color(ColorSpec::new().set_fg(Some(Color::Blue))).unwrap(),
-                    Level::Trace=> stdout.set_color(ColorSpec::new().set_fg(Some(Color::Green))).unwrap()
-                }
-            }
-
-            if self.timestamp {
-                let stdout = io::stdout();
-                let mut handle = stdout.lock();
-                let _ = handle.write_all(
-                    format!("{:?} {}\n", self.start.elapsed(), message).as_bytes());
-            } else {
-                let stdout = io::stdout();
-                let mut handle = stdout.lock();
-                let _ = handle.write_all(
-                    format!("{}\n", message).as_bytes());
-            }
+            self.write(record);
         }
     }
 
     fn flush(&self) {
-        stdout().flush().unwrap();
+        for sink in &self.sinks {
+            sink.flush();
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use log::Level;
+    use log::{Level, LevelFilter, Log};
 
     use super::SimpleLogger;
 
+    // Format a record at `level` as `logger` would for the console, coloured by level
+    fn format_colored(logger: &SimpleLogger, level: Level) -> String {
+        let record = log::Record::builder().level(level).args(format_args!("Hello")).build();
+        let mut line = termcolor::Buffer::ansi();
+        logger.format(&mut line, logger.sinks[0].formatter.as_ref(), &record, None).unwrap();
+        String::from_utf8(line.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn colored_line_is_reset() {
+        let logger = SimpleLogger::builder().build();
+        assert_eq!(format_colored(&logger, Level::Error), "\x1b[0m\x1b[31mERROR\t- Hello\x1b[0m\n");
+
+        let logger = SimpleLogger::builder().pattern("{c}{l}{/c} {m}").build();
+        assert!(format_colored(&logger, Level::Warn).ends_with("Hello\x1b[0m\n"));
+    }
+
+    #[test]
+    fn json_is_never_colored() {
+        let logger = SimpleLogger::builder().json().timestamp(false).build();
+        let line = format_colored(&logger, Level::Error);
+        assert!(line.starts_with("{\"level\":\"ERROR\","));
+        assert!(!line.contains('\x1b'));
+    }
+
+    #[test]
+    fn logfmt_is_never_colored() {
+        let logger = SimpleLogger::builder().logfmt().timestamp(false).build();
+        assert_eq!(format_colored(&logger, Level::Error), "level=error target=\"\" msg=Hello\n");
+    }
+
+    #[test]
+    fn level_scope_is_reset_by_formatter() {
+        let logger = SimpleLogger::builder().color_scope(super::ColorScope::Level).build();
+        assert_eq!(format_colored(&logger, Level::Error), "\x1b[0m\x1b[31mERROR\x1b[0m\t- Hello\n");
+
+        let logger = SimpleLogger::builder().color_scope(super::ColorScope::Level).prefix(false).build();
+        assert_eq!(format_colored(&logger, Level::Error), "Hello\n");
+    }
+
+    #[test]
+    fn uncolored_line() {
+        let logger = SimpleLogger::builder().build();
+        let record = log::Record::builder().level(Level::Info).args(format_args!("Hello")).build();
+        let mut line = termcolor::Buffer::no_color();
+        logger.format(&mut line, logger.sinks[0].formatter.as_ref(), &record, None).unwrap();
+        assert_eq!(line.into_inner(), b"INFO\t- Hello\n");
+    }
+
     #[test]
     fn no_log_level_arg() {
-        assert_eq!(super::parse_log_level(None), super::DEFAULT_LOG_LEVEL);
+        assert_eq!(SimpleLogger::builder().build().settings.level(), super::DEFAULT_LOG_LEVEL);
     }
 
     #[test]
     fn invalid_log_level_arg() {
-        assert_eq!(super::parse_log_level(Some("garbage")), super::DEFAULT_LOG_LEVEL);
+        let error = super::parse_verbosity("garbage").unwrap_err();
+        assert_eq!(error.input(), "garbage");
+        assert_eq!(SimpleLogger::builder().directives("garbage").build().settings.level(),
+                   super::DEFAULT_LOG_LEVEL);
     }
 
     #[test]
     fn info_log_level_arg() {
-        assert_eq!(super::parse_log_level(Some("INFO")), Level::Info);
+        assert_eq!(super::parse_verbosity("INFO"), Ok(LevelFilter::Info));
     }
 
     #[test]
     fn error_log_level_arg() {
-        assert_eq!(super::parse_log_level(Some("ERROR")), Level::Error);
+        assert_eq!(super::parse_verbosity("ERROR"), Ok(LevelFilter::Error));
     }
 
     #[test]
     fn parse_debug_log_level_arg() {
-        assert_eq!(super::parse_log_level(Some("DEBUG")), Level::Debug);
-        assert_eq!(super::parse_log_level(Some("debug")), Level::Debug);
+        assert_eq!(super::parse_verbosity("DEBUG"), Ok(LevelFilter::Debug));
+        assert_eq!(super::parse_verbosity("debug"), Ok(LevelFilter::Debug));
     }
 
     #[test]
@@ -214,4 +428,55 @@ mod test {
     fn init_no_level_no_prefix() {
         SimpleLogger::init_prefix(None, false);
     }
+
+    #[test]
+    fn errors_to_stderr_by_default() {
+        let logger = SimpleLogger::builder().build();
+        assert!(logger.sinks[0].use_stderr(Level::Error));
+        assert!(!logger.sinks[0].use_stderr(Level::Warn));
+        assert!(!logger.sinks[0].use_stderr(Level::Info));
+    }
+
+    #[test]
+    fn warnings_to_stderr() {
+        let logger = SimpleLogger::builder().stderr_level(LevelFilter::Warn).build();
+        assert!(logger.sinks[0].use_stderr(Level::Warn));
+        assert!(!logger.sinks[0].use_stderr(Level::Info));
+    }
+
+    #[test]
+    fn all_to_stderr() {
+        let logger = SimpleLogger::builder().target(super::Target::Stderr).build();
+        assert!(logger.sinks[0].use_stderr(Level::Trace));
+    }
+
+    #[test]
+    fn module_level_enabled() {
+        let logger = SimpleLogger::builder()
+            .directives("warn,mycrate=debug")
+            .build();
+        let metadata = |level, target| log::Metadata::builder().level(level).target(target).build();
+        assert!(logger.enabled(&metadata(Level::Debug, "mycrate::net")));
+        assert!(!logger.enabled(&metadata(Level::Debug, "hyper")));
+        assert!(logger.enabled(&metadata(Level::Warn, "hyper")));
+    }
+
+    #[test]
+    fn off_disables_all() {
+        let logger = SimpleLogger::builder().directives("off").build();
+        let metadata = log::Metadata::builder().level(Level::Error).build();
+        assert!(!logger.enabled(&metadata));
+        assert_eq!(logger.warning(format_args!("Hello")), None);
+    }
+
+    #[test]
+    fn warning() {
+        let logger = SimpleLogger::builder().build();
+        assert_eq!(logger.warning(format_args!("Hello")).unwrap(), "simplog: WARN - Hello");
+    }
+
+    #[test]
+    fn builder_init() {
+        SimpleLogger::builder().level(LevelFilter::Info).timestamp(true).init();
+    }
 }
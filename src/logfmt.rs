@@ -0,0 +1,135 @@
+use std::io::{self, Write};
+
+use log::Record;
+use termcolor::WriteColor;
+
+use crate::{Context, Formatter};
+
+// Write `value` to `out` as a logfmt value, quoting and escaping it if needed
+fn write_value<W: Write + ?Sized>(out: &mut W, value: &str) -> io::Result<()> {
+    let needs_quotes = value.is_empty()
+        || value.chars().any(|c| c == ' ' || c == '=' || c == '"' || c == '\\' || c.is_control());
+    if !needs_quotes {
+        return out.write_all(value.as_bytes());
+    }
+
+    out.write_all(b"\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => out.write_all(b"\\\"")?,
+            '\\' => out.write_all(b"\\\\")?,
+            '\n' => out.write_all(b"\\n")?,
+            '\r' => out.write_all(b"\\r")?,
+            '\t' => out.write_all(b"\\t")?,
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32)?,
+            c => write!(out, "{}", c)?,
+        }
+    }
+    out.write_all(b"\"")
+}
+
+/// A `Formatter` that writes each log line in logfmt format, as `key=value` pairs with values
+/// quoted and escaped where needed. The keys are "ts" (if timestamps are enabled), "level",
+/// "target" and "msg", followed by any key-values of the record with the "kv" feature.
+/// Output is never coloured.
+///
+/// # Example
+/// ```
+/// use log::info;
+/// use simplog::SimpleLogger;
+///
+/// SimpleLogger::builder().logfmt().init();
+/// info!("Hello World!");
+/// // Produces ts=2024-03-01T12:34:56.789Z level=info target=app msg="Hello World!"
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LogfmtFormatter;
+
+impl Formatter for LogfmtFormatter {
+    fn format(&self, out: &mut dyn WriteColor, record: &Record, context: &Context)
+        -> io::Result<()> {
+        if let Some(timestamp) = context.timestamp() {
+            out.write_all(b"ts=")?;
+            write_value(out, timestamp)?;
+            out.write_all(b" ")?;
+        }
+        write!(out, "level={}", record.level().as_str().to_lowercase())?;
+        out.write_all(b" target=")?;
+        write_value(out, record.target())?;
+        out.write_all(b" msg=")?;
+        write_value(out, &record.args().to_string())?;
+        #[cfg(feature = "kv")]
+        for (key, value) in crate::format::key_values(record) {
+            write!(out, " {}=", key)?;
+            write_value(out, &value.to_string())?;
+        }
+        Ok(())
+    }
+
+    fn uses_color(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use log::{Level, Record};
+
+    use super::{write_value, LogfmtFormatter};
+    use crate::{Context, Formatter};
+
+    fn value(value: &str) -> String {
+        let mut out = Vec::new();
+        write_value(&mut out, value).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn quoting() {
+        assert_eq!(value("plain"), "plain");
+        assert_eq!(value(""), "\"\"");
+        assert_eq!(value("two words"), "\"two words\"");
+        assert_eq!(value("a=b"), "\"a=b\"");
+        assert_eq!(value("say \"hi\""), "\"say \\\"hi\\\"\"");
+        assert_eq!(value("back\\slash"), "\"back\\\\slash\"");
+        assert_eq!(value("line 1\nline 2"), "\"line 1\\nline 2\"");
+    }
+
+    #[test]
+    fn logfmt_line() {
+        let record = Record::builder()
+            .level(Level::Info)
+            .target("app::net")
+            .args(format_args!("Hello World"))
+            .build();
+        let mut out = termcolor::Buffer::no_color();
+        let context = Context::new(Some("2024-03-01T12:34:56Z"), true, None);
+        LogfmtFormatter.format(&mut out, &record, &context).unwrap();
+        assert_eq!(String::from_utf8(out.into_inner()).unwrap(),
+                   "ts=2024-03-01T12:34:56Z level=info target=app::net msg=\"Hello World\"");
+    }
+
+    #[cfg(feature = "kv")]
+    #[test]
+    fn key_values() {
+        let key_values = [("user", log::kv::Value::from(42)), ("name", log::kv::Value::from("a b"))];
+        let record = Record::builder()
+            .level(Level::Info)
+            .target("app")
+            .args(format_args!("login"))
+            .key_values(&key_values)
+            .build();
+        let mut out = termcolor::Buffer::no_color();
+        LogfmtFormatter.format(&mut out, &record, &Context::new(None, true, None)).unwrap();
+        assert_eq!(String::from_utf8(out.into_inner()).unwrap(),
+                   "level=info target=app msg=login user=42 name=\"a b\"");
+    }
+
+    #[test]
+    fn no_timestamp() {
+        let record = Record::builder().level(Level::Warn).target("app").args(format_args!("hi")).build();
+        let mut out = termcolor::Buffer::no_color();
+        LogfmtFormatter.format(&mut out, &record, &Context::new(None, true, None)).unwrap();
+        assert_eq!(String::from_utf8(out.into_inner()).unwrap(), "level=warn target=app msg=hi");
+    }
+}
@@ -0,0 +1,309 @@
+use std::io;
+
+use log::Record;
+use termcolor::WriteColor;
+
+use crate::format::thread_name;
+use crate::{Context, Formatter, ParseError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    Timestamp,
+    Level,
+    Target,
+    Module,
+    File,
+    Line,
+    Thread,
+    ThreadId,
+    Pid,
+    Message,
+    KeyValues,
+}
+
+impl Field {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "d" | "timestamp" => Some(Field::Timestamp),
+            "l" | "level" => Some(Field::Level),
+            "t" | "target" => Some(Field::Target),
+            "M" | "module" => Some(Field::Module),
+            "f" | "file" => Some(Field::File),
+            "L" | "line" => Some(Field::Line),
+            "T" | "thread" => Some(Field::Thread),
+            "I" | "thread_id" => Some(Field::ThreadId),
+            "P" | "pid" => Some(Field::Pid),
+            "m" | "message" => Some(Field::Message),
+            "k" | "kv" => Some(Field::KeyValues),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Piece {
+    Literal(String),
+    Field(Field, Align, usize),
+    ColorStart,
+    ColorEnd,
+}
+
+/// A `Formatter` that lays out each log line according to a pattern, that is parsed once when
+/// it is created. Fields are written in braces, optionally followed by ':' and a width that the
+/// field is padded to, which can be preceded by '<', '>' or '^' to align it left (the default),
+/// right or centered. Use "{{" and "}}" for literal braces. The fields are:
+///  - `{d}` or `{timestamp}` - the timestamp, in the format set on the logger
+///  - `{l}` or `{level}` - the level of the record
+///  - `{t}` or `{target}` - the target of the record
+///  - `{M}` or `{module}` - the module path of the record
+///  - `{f}` or `{file}` - the file and line of the record, as "file:line"
+///  - `{L}` or `{line}` - the line of the record
+///  - `{T}` or `{thread}` - the name of the current thread, or its id if it has no name
+///  - `{I}` or `{thread_id}` - the id of the current thread
+///  - `{P}` or `{pid}` - the id of the process
+///  - `{m}` or `{message}` - the message of the record
+///  - `{k}` or `{kv}` - the key-values of the record as "key=value" separated by spaces, with
+///    the "kv" feature, otherwise nothing
+///  - `{c}` and `{/c}` - start and end colouring with the colour for the level of the record, if
+///    the output is coloured
+///
+/// # Example
+/// ```
+/// use log::info;
+/// use simplog::{PatternFormatter, SimpleLogger};
+///
+/// SimpleLogger::builder()
+///     .formatter(PatternFormatter::new("{d} {c}{l:5}{/c} [{t}] {m}").unwrap())
+///     .timestamp(true)
+///     .init();
+/// info!("Hello World!");
+/// // Produces "1.246717ms INFO  [rust_out] Hello World!"
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatternFormatter {
+    pieces: Vec<Piece>,
+}
+
+impl PatternFormatter {
+    /// Create a `PatternFormatter` from `pattern`, returning a `ParseError` if it is not valid
+    pub fn new(pattern: &str) -> Result<Self, ParseError> {
+        let mut pieces = Vec::new();
+        let mut literal = String::new();
+        let mut chars = pattern.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut field = String::new();
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        field.push(c);
+                    }
+                    if !closed {
+                        return Err(ParseError::pattern(pattern));
+                    }
+                    if !literal.is_empty() {
+                        pieces.push(Piece::Literal(std::mem::take(&mut literal)));
+                    }
+                    pieces.push(Self::parse_field(&field)
+                        .ok_or_else(|| ParseError::pattern(pattern))?);
+                }
+                '}' => return Err(ParseError::pattern(pattern)),
+                _ => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(literal));
+        }
+        Ok(PatternFormatter { pieces })
+    }
+
+    fn parse_field(field: &str) -> Option<Piece> {
+        let (name, spec) = field.split_once(':').unwrap_or((field, ""));
+        match name {
+            "c" if spec.is_empty() => return Some(Piece::ColorStart),
+            "/c" if spec.is_empty() => return Some(Piece::ColorEnd),
+            _ => {}
+        }
+
+        let (align, width) = match spec.chars().next() {
+            Some('<') => (Align::Left, &spec[1..]),
+            Some('>') => (Align::Right, &spec[1..]),
+            Some('^') => (Align::Center, &spec[1..]),
+            _ => (Align::Left, spec),
+        };
+        let width = if width.is_empty() { 0 } else { width.parse().ok()? };
+
+        Some(Piece::Field(Field::from_name(name)?, align, width))
+    }
+
+    // Whether the pattern includes colour markers
+    fn has_color(&self) -> bool {
+        self.pieces.contains(&Piece::ColorStart)
+    }
+
+    // Whether the pattern includes the timestamp
+    pub(crate) fn has_timestamp(&self) -> bool {
+        self.pieces.iter().any(|piece| matches!(piece, Piece::Field(Field::Timestamp, _, _)))
+    }
+
+    fn field_value(field: Field, record: &Record, context: &Context) -> String {
+        match field {
+            Field::Timestamp => context.timestamp().unwrap_or_default().to_string(),
+            Field::Level => record.level().to_string(),
+            Field::Target => record.target().to_string(),
+            Field::Module => record.module_path().unwrap_or_default().to_string(),
+            Field::File => match (record.file(), record.line()) {
+                (Some(file), Some(line)) => format!("{}:{}", file, line),
+                (Some(file), None) => file.to_string(),
+                _ => String::new(),
+            },
+            Field::Line => record.line().map(|line| line.to_string()).unwrap_or_default(),
+            Field::Thread => thread_name(),
+            Field::ThreadId => format!("{:?}", std::thread::current().id()),
+            Field::Pid => std::process::id().to_string(),
+            Field::Message => record.args().to_string(),
+            #[cfg(feature = "kv")]
+            Field::KeyValues => crate::format::key_values(record).iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect::<Vec<_>>()
+                .join(" "),
+            #[cfg(not(feature = "kv"))]
+            Field::KeyValues => String::new(),
+        }
+    }
+}
+
+impl Formatter for PatternFormatter {
+    fn format(&self, out: &mut dyn WriteColor, record: &Record, context: &Context)
+        -> io::Result<()> {
+        for piece in &self.pieces {
+            match piece {
+                Piece::Literal(literal) => out.write_all(literal.as_bytes())?,
+                Piece::Field(Field::Message, _, 0) => write!(out, "{}", record.args())?,
+                Piece::Field(field, align, width) => {
+                    let value = Self::field_value(*field, record, context);
+                    match align {
+                        Align::Left => write!(out, "{:<width$}", value, width = width)?,
+                        Align::Right => write!(out, "{:>width$}", value, width = width)?,
+                        Align::Center => write!(out, "{:^width$}", value, width = width)?,
+                    }
+                }
+                Piece::ColorStart => context.set_color(out)?,
+                Piece::ColorEnd => context.reset(out)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn colors(&self) -> bool {
+        self.has_color()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use log::{Level, Record};
+    use termcolor::{Color, ColorSpec};
+
+    use super::PatternFormatter;
+    use crate::{Context, Formatter};
+
+    fn format(pattern: &str, color: Option<ColorSpec>) -> String {
+        let formatter = PatternFormatter::new(pattern).unwrap();
+        let record = Record::builder()
+            .level(Level::Warn)
+            .target("mycrate::net")
+            .module_path(Some("mycrate::net"))
+            .file(Some("src/net.rs"))
+            .line(Some(42))
+            .args(format_args!("Hello World"))
+            .build();
+        let mut out = termcolor::Buffer::ansi();
+        formatter.format(&mut out, &record, &Context::new(Some("1.2ms"), true, color)).unwrap();
+        String::from_utf8(out.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn fields() {
+        assert_eq!(format("{d} {l:5} [{t}] {m}", None), "1.2ms WARN  [mycrate::net] Hello World");
+        assert_eq!(format("{M} {f} {L}", None), "mycrate::net src/net.rs:42 42");
+        assert_eq!(format("{timestamp}|{level}|{message}", None), "1.2ms|WARN|Hello World");
+        assert_eq!(format("{P}", None), std::process::id().to_string());
+    }
+
+    #[cfg(feature = "kv")]
+    #[test]
+    fn key_values() {
+        let key_values = [("user", log::kv::Value::from(42)), ("ok", log::kv::Value::from(true))];
+        let record = Record::builder()
+            .level(Level::Info)
+            .args(format_args!("login"))
+            .key_values(&key_values)
+            .build();
+        let mut out = termcolor::Buffer::ansi();
+        PatternFormatter::new("{m} [{k}]").unwrap()
+            .format(&mut out, &record, &Context::new(None, true, None)).unwrap();
+        assert_eq!(String::from_utf8(out.into_inner()).unwrap(), "login [user=42 ok=true]");
+    }
+
+    #[test]
+    fn alignment() {
+        assert_eq!(format("[{l:>6}]", None), "[  WARN]");
+        assert_eq!(format("[{l:^6}]", None), "[ WARN ]");
+        assert_eq!(format("[{l:<6}]", None), "[WARN  ]");
+        assert_eq!(format("[{l:2}]", None), "[WARN]");
+    }
+
+    #[test]
+    fn escaped_braces() {
+        assert_eq!(format("{{{l}}}", None), "{WARN}");
+    }
+
+    #[test]
+    fn thread() {
+        let name = std::thread::Builder::new().name("worker".into())
+            .spawn(|| format("{T}", None)).unwrap().join().unwrap();
+        assert_eq!(name, "worker");
+    }
+
+    #[test]
+    fn color_markers() {
+        let mut red = ColorSpec::new();
+        red.set_fg(Some(Color::Red));
+        assert_eq!(format("{c}{l}{/c} {m}", Some(red)), "\x1b[0m\x1b[31mWARN\x1b[0m Hello World");
+        assert_eq!(format("{c}{l}{/c} {m}", None), "WARN Hello World");
+        assert!(PatternFormatter::new("{c}{l}{/c}").unwrap().has_color());
+        assert!(!PatternFormatter::new("{l}").unwrap().has_color());
+    }
+
+    #[test]
+    fn invalid_patterns() {
+        assert!(PatternFormatter::new("{x}").is_err());
+        assert!(PatternFormatter::new("{l:abc}").is_err());
+        assert!(PatternFormatter::new("{l").is_err());
+        assert!(PatternFormatter::new("l}").is_err());
+        assert_eq!(PatternFormatter::new("{x}").unwrap_err().to_string(),
+                   "invalid log line pattern '{x}'");
+    }
+}
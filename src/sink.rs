@@ -0,0 +1,258 @@
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use atty::Stream;
+use log::{Level, LevelFilter};
+use termcolor::{Buffer, BufferWriter, ColorChoice};
+
+use crate::file::FileSink;
+use crate::timestamp::{Clock, Precision};
+use crate::writer::WriterSink;
+use crate::{ColorMode, Formatter, JsonFormatter, LogfmtFormatter, ParseError, Rotation,
+            TimestampFormat};
+
+// Where the log lines of a `Sink` are written
+#[derive(Clone)]
+enum Output {
+    // STDOUT, or STDERR for the levels up to `stderr_level`
+    Console { stderr_level: LevelFilter },
+    File { path: PathBuf, append: bool, rotation: Option<Rotation> },
+    Writer(Arc<WriterSink>),
+}
+
+/// An output for log lines with its own log level, formatter, timestamp format and colour mode,
+/// added to a `SimpleLogger` using `SimpleLoggerBuilder::sink()`. When any sinks are added they
+/// are used instead of the output set by `target()`, `file()` or `writer()` on the builder.
+///
+/// The level of a sink replaces the logger's default level for that sink, and caps the levels
+/// set for specific modules with `module_level()` or `directives()`: a module set to `Debug` is
+/// still only logged at `Error` to a sink with level `Error`. Sinks without their own timestamp
+/// format share the logger's timestamps, and all sinks share its prefix, palette and other
+/// settings.
+///
+/// # Example
+/// ```
+/// use log::LevelFilter;
+/// use simplog::{ColorMode, SimpleLogger, Sink};
+///
+/// let path = std::env::temp_dir().join("sink_example.log");
+/// SimpleLogger::builder()
+///     .sink(Sink::stderr().level(LevelFilter::Error).color_mode(ColorMode::Always))
+///     .sink(Sink::file(path).level(LevelFilter::Info).json())
+///     .sink(Sink::writer(Vec::new()).level(LevelFilter::Trace))
+///     .init();
+/// ```
+#[derive(Clone)]
+pub struct Sink {
+    output: Output,
+    level: Option<LevelFilter>,
+    formatter: Option<Arc<dyn Formatter>>,
+    timestamp_format: Option<TimestampFormat>,
+    color: ColorMode,
+}
+
+impl Sink {
+    fn new(output: Output) -> Self {
+        Sink { output, level: None, formatter: None, timestamp_format: None, color: ColorMode::Auto }
+    }
+
+    /// A sink that writes to STDOUT
+    pub fn stdout() -> Self {
+        Sink::console(LevelFilter::Off)
+    }
+
+    /// A sink that writes to STDERR
+    pub fn stderr() -> Self {
+        Sink::console(LevelFilter::Trace)
+    }
+
+    // A sink that writes to STDOUT, except for the levels up to `stderr_level` which are written
+    // to STDERR
+    pub(crate) fn console(stderr_level: LevelFilter) -> Self {
+        Sink::new(Output::Console { stderr_level })
+    }
+
+    /// A sink that writes to the file at `path`, which is created if it doesn't exist and
+    /// appended to if it does. It is not coloured unless `color_mode()` is used.
+    pub fn file<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref().to_path_buf();
+        Sink::new(Output::File { path, append: true, rotation: None }).color_mode(ColorMode::Never)
+    }
+
+    /// A sink that writes to `writer`, such as a socket, pipe or in-memory buffer, see
+    /// `SimpleLoggerBuilder::writer()`
+    pub fn writer<W: Write + Send + 'static>(writer: W) -> Self {
+        Sink::new(Output::Writer(Arc::new(WriterSink::new(writer))))
+    }
+
+    /// Set the maximum log level (verbosity) written to this sink. By default the logger's
+    /// level is used, which can be changed at runtime with a `LoggerHandle`.
+    pub fn level(mut self, level: LevelFilter) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Set the `Formatter` used for this sink. By default the logger's formatter is used.
+    pub fn formatter<F: Formatter + 'static>(mut self, formatter: F) -> Self {
+        self.formatter = Some(Arc::new(formatter));
+        self
+    }
+
+    /// Write each log line to this sink as a JSON object, using `JsonFormatter`, with UTC
+    /// timestamps. Call `timestamp_format()` after this to use a different timestamp format.
+    pub fn json(self) -> Self {
+        self.formatter(JsonFormatter).timestamp_format(TimestampFormat::Utc)
+    }
+
+    /// Write each log line to this sink as logfmt key=value pairs, using `LogfmtFormatter`, with
+    /// UTC timestamps. Call `timestamp_format()` after this to use a different timestamp format.
+    pub fn logfmt(self) -> Self {
+        self.formatter(LogfmtFormatter).timestamp_format(TimestampFormat::Utc)
+    }
+
+    /// Set the format of the timestamps of this sink, and always write them whether or not
+    /// timestamps are enabled on the logger. By default the logger's timestamps are used. A
+    /// `TimestampFormat::Custom` pattern that cannot be parsed is handled as described for
+    /// `SimpleLoggerBuilder::strict()`, using the logger's timestamps instead when it is ignored.
+    pub fn timestamp_format(mut self, format: TimestampFormat) -> Self {
+        self.timestamp_format = Some(format);
+        self
+    }
+
+    /// Set when log lines written to this sink are coloured by level. The default for a file sink
+    /// is `ColorMode::Never`, and for other sinks `ColorMode::Auto`, for which writers are not
+    /// terminals.
+    pub fn color_mode(mut self, mode: ColorMode) -> Self {
+        self.color = mode;
+        self
+    }
+
+    /// Set whether a file sink is appended to if it already exists (the default), or
+    /// truncated when the logger is built. Not used by other sinks.
+    pub fn append(mut self, append: bool) -> Self {
+        if let Output::File { append: file_append, .. } = &mut self.output {
+            *file_append = append;
+        }
+        self
+    }
+
+    /// Set the policy used to rotate a file sink. Not used by other sinks.
+    pub fn rotate(mut self, rotation: Rotation) -> Self {
+        if let Output::File { rotation: file_rotation, .. } = &mut self.output {
+            *file_rotation = Some(rotation);
+        }
+        self
+    }
+
+    // The clock for the timestamps of this sink with `precision`, or `None` if it uses the
+    // logger's timestamps
+    pub(crate) fn clock(&self, precision: Precision) -> Result<Option<Clock>, ParseError> {
+        self.timestamp_format.as_ref()
+            .map(|format| Clock::new(format.clone(), precision))
+            .transpose()
+    }
+
+    // Open the sink for writing, using `formatter` if it doesn't have its own and `clock` for its
+    // timestamps
+    pub(crate) fn open(&self, formatter: &Arc<dyn Formatter>, clock: Option<Clock>)
+        -> io::Result<SinkOutput> {
+        let destination = match &self.output {
+            Output::Console { stderr_level } => {
+                let choice = |stream| match self.color.enabled(stream) {
+                    true => ColorChoice::Always,
+                    false => ColorChoice::Never,
+                };
+                Destination::Console {
+                    stderr_level: *stderr_level,
+                    stdout: Arc::new(BufferWriter::stdout(choice(Stream::Stdout))),
+                    stderr: Arc::new(BufferWriter::stderr(choice(Stream::Stderr))),
+                }
+            }
+            Output::File { path, append, rotation } => {
+                let file = FileSink::open(path, *append, rotation.clone())?;
+                Destination::File(Arc::new(file), self.color.enabled_for_writer())
+            }
+            Output::Writer(writer) => {
+                Destination::Writer(writer.clone(), self.color.enabled_for_writer())
+            }
+        };
+        Ok(SinkOutput {
+            destination,
+            level: self.level,
+            formatter: self.formatter.clone().unwrap_or_else(|| formatter.clone()),
+            clock,
+        })
+    }
+}
+
+// Where an opened sink writes, and whether the lines written there are coloured. The console is
+// written using a `BufferWriter` for each stream, which colours lines with the Windows console
+// API on consoles that don't support ANSI escape codes.
+#[derive(Clone)]
+enum Destination {
+    Console { stderr_level: LevelFilter, stdout: Arc<BufferWriter>, stderr: Arc<BufferWriter> },
+    File(Arc<FileSink>, bool),
+    Writer(Arc<WriterSink>, bool),
+}
+
+/*
+    A `Sink` that has been opened for writing by a `SimpleLogger`. The logger's own output, set
+    by `target()`, `file()` or `writer()` on the builder, is opened as sinks too.
+*/
+#[derive(Clone)]
+pub(crate) struct SinkOutput {
+    destination: Destination,
+    pub(crate) level: Option<LevelFilter>,
+    pub(crate) formatter: Arc<dyn Formatter>,
+    pub(crate) clock: Option<Clock>,
+}
+
+impl SinkOutput {
+    // Determine if a line for `level` is written to STDERR instead of STDOUT
+    pub(crate) fn use_stderr(&self, level: Level) -> bool {
+        matches!(self.destination, Destination::Console { stderr_level, .. } if level <= stderr_level)
+    }
+
+    // A buffer to format a line for `level` into, that colours it if colour is enabled for
+    // where it will be written
+    pub(crate) fn buffer(&self, level: Level) -> Buffer {
+        match &self.destination {
+            Destination::Console { stderr, .. } if self.use_stderr(level) => stderr.buffer(),
+            Destination::Console { stdout, .. } => stdout.buffer(),
+            Destination::File(_, true) | Destination::Writer(_, true) => Buffer::ansi(),
+            Destination::File(_, false) | Destination::Writer(_, false) => Buffer::no_color(),
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn is_console(&self) -> bool {
+        matches!(self.destination, Destination::Console { .. })
+    }
+
+    // Write a whole log line for `level`, formatted into a buffer from `buffer()`, with a single
+    // write
+    pub(crate) fn write_line(&self, level: Level, line: &Buffer) {
+        match &self.destination {
+            Destination::Console { stderr, .. } if self.use_stderr(level) => {
+                let _ = stderr.print(line);
+            }
+            Destination::Console { stdout, .. } => {
+                let _ = stdout.print(line);
+            }
+            Destination::File(file, _) => file.write_line(line.as_slice()),
+            Destination::Writer(writer, _) => writer.write_line(line.as_slice()),
+        }
+    }
+
+    pub(crate) fn flush(&self) {
+        match &self.destination {
+            Destination::Console { .. } => {
+                let _ = io::stdout().flush();
+                let _ = io::stderr().flush();
+            }
+            Destination::File(file, _) => file.flush(),
+            Destination::Writer(writer, _) => writer.flush(),
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+
+use crate::ParseError;
+
+/// The format of the timestamp prefixed to each log line, when timestamps are enabled
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// The time elapsed since the logger was built, e.g. "1.246717ms"
+    Elapsed,
+    /// The time elapsed since the logger was built, in seconds with a fixed number of decimal
+    /// places and padded to a fixed width so that it lines up, e.g. "    1.246s"
+    ElapsedFixed,
+    /// The time elapsed since the previous log line was written, in the same fixed width format
+    /// as `ElapsedFixed`, e.g. "    0.012s"
+    Delta,
+    /// The UTC date and time in RFC 3339 format, e.g. "2024-03-01T12:34:56.789Z"
+    Utc,
+    /// The local date and time in RFC 3339 format, e.g. "2024-03-01T13:34:56.789+01:00"
+    Local,
+    /// The number of seconds since the unix epoch, e.g. "1709296496.789"
+    UnixSeconds,
+    /// The number of whole milliseconds since the unix epoch, e.g. "1709296496789"
+    UnixMillis,
+    /// The local date and time formatted using a strftime-like pattern, as supported by
+    /// `chrono::format::strftime`, e.g. "%Y-%m-%d %H:%M:%S%.3f"
+    Custom(String),
+    /// The UTC date and time formatted using a strftime-like pattern, as for `Custom`
+    CustomUtc(String),
+}
+
+/// The precision of the fractional seconds shown in a timestamp, for all timestamp formats except
+/// `Elapsed`, `UnixMillis` and the custom ones
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Precision {
+    /// Whole seconds only
+    Seconds,
+    /// Milliseconds (3 digits)
+    Millis,
+    /// Microseconds (6 digits)
+    Micros,
+    /// Nanoseconds (9 digits)
+    Nanos,
+}
+
+impl Precision {
+    fn digits(&self) -> usize {
+        match self {
+            Precision::Seconds => 0,
+            Precision::Millis => 3,
+            Precision::Micros => 6,
+            Precision::Nanos => 9,
+        }
+    }
+
+    fn seconds_format(&self) -> SecondsFormat {
+        match self {
+            Precision::Seconds => SecondsFormat::Secs,
+            Precision::Millis => SecondsFormat::Millis,
+            Precision::Micros => SecondsFormat::Micros,
+            Precision::Nanos => SecondsFormat::Nanos,
+        }
+    }
+}
+
+// The width that the whole seconds of fixed width elapsed times are padded to
+const SECONDS_WIDTH: usize = 5;
+
+/*
+    Produces the timestamps for log lines, in the format chosen when the logger was built.
+    Custom patterns are parsed once when it is created, and the time of the previous line is
+    kept (as nanoseconds since the start) for the `Delta` format.
+*/
+#[derive(Clone, Debug)]
+pub(crate) struct Clock {
+    start: Instant,
+    format: TimestampFormat,
+    precision: Precision,
+    pattern: Vec<Item<'static>>,
+    previous: Arc<AtomicU64>,
+}
+
+impl Clock {
+    pub(crate) fn new(format: TimestampFormat, precision: Precision) -> Result<Self, ParseError> {
+        let pattern = match &format {
+            TimestampFormat::Custom(pattern) | TimestampFormat::CustomUtc(pattern) =>
+                StrftimeItems::new(pattern).parse_to_owned()
+                    .map_err(|_| ParseError::timestamp(pattern))?,
+            _ => Vec::new(),
+        };
+        Ok(Clock { start: Instant::now(), format, precision, pattern, previous: Arc::default() })
+    }
+
+    // A clock for `TimestampFormat::Elapsed`, which unlike a custom format cannot fail to parse
+    pub(crate) fn elapsed(precision: Precision) -> Self {
+        Clock {
+            start: Instant::now(),
+            format: TimestampFormat::Elapsed,
+            precision,
+            pattern: Vec::new(),
+            previous: Arc::default(),
+        }
+    }
+
+    // Write `duration` in seconds, with the precision chosen and padded to a fixed width
+    fn write_fixed(&self, out: &mut String, duration: Duration) -> std::fmt::Result {
+        match self.precision.digits() {
+            0 => write!(out, "{:>width$}s", duration.as_secs(), width = SECONDS_WIDTH),
+            digits => write!(out, "{:>width$}.{:0digits$}s", duration.as_secs(),
+                duration.subsec_nanos() / 10u32.pow(9 - digits as u32),
+                width = SECONDS_WIDTH, digits = digits),
+        }
+    }
+
+    // The time since the previous call, or since the start for the first call
+    fn delta(&self) -> Duration {
+        let elapsed = self.start.elapsed().as_nanos() as u64;
+        let previous = self.previous.swap(elapsed, Ordering::Relaxed);
+        Duration::from_nanos(elapsed.saturating_sub(previous))
+    }
+
+    // Write the timestamp for `now` to `out`
+    pub(crate) fn write(&self, out: &mut String, now: SystemTime) {
+        let _ = match &self.format {
+            TimestampFormat::Elapsed => write!(out, "{:?}", self.start.elapsed()),
+            TimestampFormat::ElapsedFixed => self.write_fixed(out, self.start.elapsed()),
+            TimestampFormat::Delta => self.write_fixed(out, self.delta()),
+            TimestampFormat::Utc => write!(out, "{}", DateTime::<Utc>::from(now)
+                .to_rfc3339_opts(self.precision.seconds_format(), true)),
+            TimestampFormat::Local => write!(out, "{}", DateTime::<Local>::from(now)
+                .to_rfc3339_opts(self.precision.seconds_format(), false)),
+            TimestampFormat::UnixSeconds => {
+                let since_epoch = now.duration_since(UNIX_EPOCH).unwrap_or_default();
+                match self.precision.digits() {
+                    0 => write!(out, "{}", since_epoch.as_secs()),
+                    digits => write!(out, "{}.{:0width$}", since_epoch.as_secs(),
+                        since_epoch.subsec_nanos() / 10u32.pow(9 - digits as u32), width = digits),
+                }
+            }
+            TimestampFormat::UnixMillis => write!(out, "{}",
+                now.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis()),
+            TimestampFormat::Custom(_) => write!(out, "{}",
+                DateTime::<Local>::from(now).format_with_items(self.pattern.iter())),
+            TimestampFormat::CustomUtc(_) => write!(out, "{}",
+                DateTime::<Utc>::from(now).format_with_items(self.pattern.iter())),
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{Clock, Precision, TimestampFormat};
+
+    fn timestamp(format: TimestampFormat, precision: Precision) -> String {
+        let now = UNIX_EPOCH + Duration::from_nanos(1_709_296_496_789_123_456);
+        let mut out = String::new();
+        Clock::new(format, precision).unwrap().write(&mut out, now);
+        out
+    }
+
+    #[test]
+    fn utc() {
+        assert_eq!(timestamp(TimestampFormat::Utc, Precision::Millis), "2024-03-01T12:34:56.789Z");
+        assert_eq!(timestamp(TimestampFormat::Utc, Precision::Seconds), "2024-03-01T12:34:56Z");
+        assert_eq!(timestamp(TimestampFormat::Utc, Precision::Nanos),
+                   "2024-03-01T12:34:56.789123456Z");
+    }
+
+    #[test]
+    fn unix() {
+        assert_eq!(timestamp(TimestampFormat::UnixSeconds, Precision::Seconds), "1709296496");
+        assert_eq!(timestamp(TimestampFormat::UnixSeconds, Precision::Micros), "1709296496.789123");
+        assert_eq!(timestamp(TimestampFormat::UnixMillis, Precision::Nanos), "1709296496789");
+    }
+
+    #[test]
+    fn custom() {
+        let format = TimestampFormat::CustomUtc("%Y/%m/%d %H:%M:%S%.3f".into());
+        assert_eq!(timestamp(format, Precision::Seconds), "2024/03/01 12:34:56.789");
+    }
+
+    #[test]
+    fn local_is_rfc3339() {
+        let local = timestamp(TimestampFormat::Local, Precision::Millis);
+        let parsed = chrono::DateTime::parse_from_rfc3339(&local).unwrap();
+        assert_eq!(parsed.timestamp_millis(), 1_709_296_496_789);
+    }
+
+    #[test]
+    fn fixed_width() {
+        let clock = Clock::new(TimestampFormat::ElapsedFixed, Precision::Micros).unwrap();
+        let mut out = String::new();
+        clock.write_fixed(&mut out, Duration::from_nanos(12_345_678_901)).unwrap();
+        assert_eq!(out, "   12.345678s");
+        out.clear();
+        clock.write_fixed(&mut out, Duration::from_micros(1)).unwrap();
+        assert_eq!(out, "    0.000001s");
+        let clock = Clock::new(TimestampFormat::ElapsedFixed, Precision::Seconds).unwrap();
+        out.clear();
+        clock.write_fixed(&mut out, Duration::from_millis(1500)).unwrap();
+        assert_eq!(out, "    1s");
+    }
+
+    #[test]
+    fn delta_since_previous() {
+        let clock = Clock::new(TimestampFormat::Delta, Precision::Millis).unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(clock.delta() >= Duration::from_millis(20));
+        assert!(clock.delta() < Duration::from_millis(20));
+    }
+
+    #[test]
+    fn invalid_custom() {
+        let error = Clock::new(TimestampFormat::Custom("%Q".into()), Precision::Millis).unwrap_err();
+        assert_eq!(error.to_string(), "invalid timestamp pattern '%Q'");
+    }
+}
@@ -0,0 +1,91 @@
+use std::fmt;
+use std::io::Write;
+use std::sync::Mutex;
+
+/*
+    A writer supplied by the user, such as a socket, pipe or in-memory buffer, that log lines are
+    written to. As for a `FileSink` each line is written with a single call while holding the
+    lock, so lines written from different threads are never interleaved.
+*/
+pub(crate) struct WriterSink {
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl fmt::Debug for WriterSink {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WriterSink").finish_non_exhaustive()
+    }
+}
+
+impl WriterSink {
+    pub(crate) fn new<W: Write + Send + 'static>(writer: W) -> Self {
+        WriterSink { writer: Mutex::new(Box::new(writer)) }
+    }
+
+    pub(crate) fn write_line(&self, line: &[u8]) {
+        if let Ok(mut writer) = self.writer.lock() {
+            let _ = writer.write_all(line);
+        }
+    }
+
+    pub(crate) fn flush(&self) {
+        if let Ok(mut writer) = self.writer.lock() {
+            let _ = writer.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::io::{self, Write};
+    use std::sync::{Arc, Mutex};
+
+    use super::WriterSink;
+
+    // A writer that records what is written, and whether it has been flushed, which can be read
+    // while a sink or logger owns a clone of it
+    #[derive(Clone, Default)]
+    pub(crate) struct Shared(Arc<Mutex<(Vec<u8>, bool)>>);
+
+    impl Shared {
+        pub(crate) fn contents(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().0.clone()).unwrap()
+        }
+
+        pub(crate) fn flushed(&self) -> bool {
+            self.0.lock().unwrap().1
+        }
+    }
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().0.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.0.lock().unwrap().1 = true;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn write_and_flush() {
+        let shared = Shared::default();
+        let sink = WriterSink::new(shared.clone());
+        sink.write_line(b"one\n");
+        sink.write_line(b"two\n");
+        assert!(!shared.flushed());
+        sink.flush();
+        assert_eq!(shared.contents(), "one\ntwo\n");
+        assert!(shared.flushed());
+    }
+
+    #[test]
+    fn boxed_writer() {
+        let shared = Shared::default();
+        let writer: Box<dyn Write + Send> = Box::new(shared.clone());
+        WriterSink::new(writer).write_line(b"boxed\n");
+        assert_eq!(shared.contents(), "boxed\n");
+    }
+}
@@ -0,0 +1,14 @@
+/*
+    Installing a logger changes state that is global to the process, so these checks are kept in
+    a test binary of their own, where no other tests install loggers at the same time.
+*/
+use log::LevelFilter;
+use simplog::SimpleLogger;
+
+#[test]
+fn second_try_init_fails() {
+    SimpleLogger::try_init(None).expect("Could not install the first logger");
+    log::set_max_level(LevelFilter::Warn);
+    assert!(SimpleLogger::try_init(Some("trace")).is_err());
+    assert_eq!(log::max_level(), LevelFilter::Warn);
+}